@@ -2,52 +2,106 @@
 use std::vec::Drain;
 
 use image::ImageBuffer;
-use wasm_bindgen::{prelude::*, Clamped};
-use web_sys::ImageData;
 
-use super::{element::{Element, EventResponse, Mouse}, style::WHITE, Style};
+use super::{element::{Button, CanvasElement, Element, ElementId, Elements, EventResponse, Gesture, Key, Mouse, MOUSE_POINTER}, render_target::{CanvasTarget, ImageTarget, RenderTarget}, style::WHITE, Painter, Style};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Callback {
-    ButtonClicked(*const super::element::Button),
+    ButtonClicked(ElementId<Button>),
+    /// The pointer was pressed on a canvas. The index is that of the topmost shape under it.
+    ShapePressed(ElementId<CanvasElement>, usize),
+}
+
+/// A pointer which is currently pressed
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Pointer {
+    pub id: i32,
+    pub x: u32,
+    pub y: u32,
+    /// Index of the element which captured the pointer
+    pub capture: Option<usize>,
 }
 
 pub struct Applet {
     width: u32,
     height: u32,
-    name: String,
+    target: Box<dyn RenderTarget>,
     pub style: Style,
+    elements: Elements,
     
     callbacks: Vec<Callback>,
-    mouse_down: bool,
+    pointers: Vec<Pointer>,
+    focus: Option<usize>,
     buffer: ImageBuffer<image::Rgba<u8>, Vec<u8>>,
 }
 
 impl Applet {
+    /// Create an applet which draws to the HTML canvas with id `name`
     pub fn new(width: u32, height: u32, name: String, style: Style) -> Self {
-        let window = web_sys::window().expect("No global `window` exists");
-        let document = window.document().expect("Should have a document on window");
-        let canvas = document.get_element_by_id(&name).expect("Could not find the canvas");
-        canvas.set_attribute("width", &width.to_string()).expect("The provided HTML object was not a canvas");
-        canvas.set_attribute("height", &height.to_string()).expect("The provided HTML object was not a canvas");
+        let target = CanvasTarget::new(name, width, height);
+        Self::with_target(width, height, style, Box::new(target))
+    }
 
+    /// Create an applet which keeps its frames in memory. Use this to run applets outside the browser.
+    pub fn headless(width: u32, height: u32, style: Style) -> Self {
+        Self::with_target(width, height, style, Box::new(ImageTarget::new()))
+    }
+
+    /// Create an applet which presents its frames to an arbitrary render target
+    pub fn with_target(width: u32, height: u32, style: Style, target: Box<dyn RenderTarget>) -> Self {
         let buffer = ImageBuffer::new(width, height);
         let callbacks = Vec::new();
 
         Self {
             width,
             height,
-            name,
+            target,
             style,
+            elements: Elements::new(),
 
             buffer,
             callbacks,
-            mouse_down: false,
+            pointers: Vec::new(),
+            focus: None,
         }
     }
 
-    pub fn tick(&mut self, elements: Vec<*mut  dyn Element>) -> Drain<Callback> {
-        for element in elements {
-            let element = unsafe { &mut *element };
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The frame produced by the last call to `render`
+    pub fn frame(&self) -> &ImageBuffer<image::Rgba<u8>, Vec<u8>> {
+        &self.buffer
+    }
+
+    /// Give an element to the applet and return a handle to it. Elements are drawn in the order they are added
+    /// and receive events in the same order.
+    pub fn add<T: Element>(&mut self, element: T) -> ElementId<T> {
+        self.elements.add(element)
+    }
+
+    /// The element a handle refers to. Panics if the handle comes from another applet.
+    pub fn get<T: Element>(&self, id: ElementId<T>) -> &T {
+        self.elements.get(id)
+    }
+
+    /// The element a handle refers to, to change. Panics if the handle comes from another applet.
+    pub fn get_mut<T: Element>(&mut self, id: ElementId<T>) -> &mut T {
+        self.elements.get_mut(id)
+    }
+
+    /// Number of elements added to the applet
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn tick(&mut self) -> Drain<'_, Callback> {
+        for element in self.elements.iter_mut() {
             if let Some(callback) = element.tick() {
                 self.callbacks.push(callback);
             }
@@ -55,16 +109,12 @@ impl Applet {
         self.callbacks.drain(..)
     }
 
-    pub fn render(&mut self, elements: &[*const dyn Element]) {
-        // Clear
-        for (_, _, pixel) in self.buffer.enumerate_pixels_mut() {
-            *pixel = WHITE;
-        }
-
-        // Draw
-        for element in elements {
-            let element = unsafe { &**element };
-            element.draw(&mut self.buffer, &self.style);
+    pub fn render(&mut self) {
+        let mut painter = Painter::new(&mut self.buffer);
+        painter.set_linear_blending(self.style.linear_blending);
+        painter.fill(WHITE);
+        for element in self.elements.iter() {
+            element.draw(&mut painter, &self.style);
         }
 
         // Border
@@ -77,79 +127,261 @@ impl Applet {
         //     self.buffer[(self.width-1, j)] = BLACK;
         // }
 
-        // Commit image buffer to the canvas
-        let window = web_sys::window().unwrap();
-        let document = window.document().expect("Could not get document");
-        let canvas = document
-            .get_element_by_id(&self.name)
-            .unwrap()
-            .dyn_into::<web_sys::HtmlCanvasElement>().unwrap();
-        let context = canvas
-            .get_context("2d").unwrap()
-            .unwrap()
-            .dyn_into::<web_sys::CanvasRenderingContext2d>().unwrap();
-        let clamped_buf: Clamped<&[u8]> = Clamped(self.buffer.as_raw());
-        let image_data_temp = 
-            ImageData::new_with_u8_clamped_array_and_sh(clamped_buf, self.width, self.height).unwrap();
-        context.put_image_data(&image_data_temp, 0.0, 0.0).unwrap();
-    }
-
-    pub fn mouse_button_down(&mut self, x: u32, y: u32, elements: Vec<*mut dyn Element>) {
-        self.mouse_down = true;
-        let mouse = Mouse {
-            x,
-            y,
-            down: self.mouse_down
-        };
-        for element in elements {
-            let element = unsafe { &mut *element };
-            match element.mouse_button_down(mouse) {
-                EventResponse::NoEvent => (),
-                EventResponse::Responded => return,
-                EventResponse::PlaceCallback(c) => {
-                    self.callbacks.push(c);
-                    return;
-                },
+        self.target.present(&self.buffer);
+    }
+
+    pub fn mouse_button_down(&mut self, x: u32, y: u32) {
+        self.pointer_down(MOUSE_POINTER, x, y);
+    }
+
+    pub fn mouse_button_up(&mut self, x: u32, y: u32) {
+        self.pointer_up(MOUSE_POINTER, x, y);
+    }
+
+    pub fn mouse_move(&mut self, x: u32, y: u32) {
+        self.pointer_move(MOUSE_POINTER, x, y);
+    }
+
+    /// Handle the mouse wheel. `delta` is the `deltaY` of the DOM `WheelEvent` in pixels.
+    /// Returns true if an element used the wheel, in which case the page should not scroll.
+    pub fn wheel(&mut self, x: u32, y: u32, delta: f32) -> bool {
+        let mouse = Mouse { x, y, down: false, id: MOUSE_POINTER };
+        self.dispatch(|element| element.wheel(mouse, delta)).is_some()
+    }
+
+    pub fn double_click(&mut self, x: u32, y: u32) {
+        let mouse = Mouse { x, y, down: false, id: MOUSE_POINTER };
+        self.dispatch(|element| element.double_click(mouse));
+    }
+
+    /// Handle a pointer (mouse, pen or touch) being pressed. `id` is the `pointerId` of the DOM `PointerEvent`.
+    /// The element which responds captures the pointer until it is released.
+    pub fn pointer_down(&mut self, id: i32, x: u32, y: u32) {
+        self.pointers.retain(|p| p.id != id);
+        let mouse = Mouse { x, y, down: true, id };
+        let capture = self.dispatch(|element| element.mouse_button_down(mouse));
+        self.pointers.push(Pointer { id, x, y, capture });
+
+        // A second pointer on an element which takes gestures turns both pointers into a gesture
+        if let Some(index) = capture {
+            if self.elements.get_dyn(index).wants_gestures() {
+                let captured = self.pointers.iter().filter(|p| p.capture == Some(index)).collect::<Vec<_>>();
+                if captured.len() == 2 {
+                    let first = *captured[0];
+                    let element = self.elements.get_dyn_mut(index);
+                    element.pointer_cancel(Mouse { x: first.x, y: first.y, down: true, id: first.id });
+                    element.pointer_cancel(mouse);
+                }
             }
         }
+
+        // Clicking an element focuses it, and clicking anywhere else clears the focus
+        let focus = capture.filter(|index| self.elements.get_dyn(*index).focusable());
+        self.set_focus_index(focus);
     }
 
-    pub fn mouse_button_up(&mut self, x: u32, y: u32, elements: Vec<*mut dyn Element>) {
-        self.mouse_down = false;
-        let mouse = Mouse {
-            x,
-            y,
-            down: self.mouse_down
+    /// Handle a pointer being released. Only the element which captured the pointer hears about it, and if none did,
+    /// the release goes to the elements under it.
+    pub fn pointer_up(&mut self, id: i32, x: u32, y: u32) {
+        let Some(pointer) = self.pointers.iter().find(|p| p.id == id).copied() else { return };
+        let in_gesture = pointer.capture.is_some_and(|index| self.gesture_pointers(index).is_some());
+        self.pointers.retain(|p| p.id != id);
+        if in_gesture {
+            return;
+        }
+
+        let mouse = Mouse { x, y, down: false, id };
+        if let Some(index) = pointer.capture {
+            let response = self.elements.get_dyn_mut(index).mouse_button_up(mouse);
+            self.respond(response);
+            return;
+        }
+        self.dispatch(|element| element.mouse_button_up(mouse));
+    }
+
+    /// Handle a pointer moving, whether or not it is pressed
+    pub fn pointer_move(&mut self, id: i32, x: u32, y: u32) {
+        let Some(pointer) = self.pointers.iter_mut().find(|p| p.id == id) else {
+            // Hovering
+            let mouse = Mouse { x, y, down: false, id };
+            self.dispatch(|element| element.mouse_move(mouse));
+            return;
         };
-        for element in elements {
-            let element = unsafe { &mut *element };
-            match element.mouse_button_up(mouse) {
-                EventResponse::NoEvent => (),
-                EventResponse::Responded => return,
-                EventResponse::PlaceCallback(c) => {
-                    self.callbacks.push(c);
-                    return;
-                },
+        let previous = *pointer;
+        pointer.x = x;
+        pointer.y = y;
+        let mouse = Mouse { x, y, down: true, id };
+
+        match previous.capture {
+            Some(index) => {
+                if let Some((a, b)) = self.gesture_pointers(index) {
+                    let (a_old, b_old) = if a.id == id { (previous, b) } else { (a, previous) };
+                    for gesture in gestures_between((a_old, b_old), (a, b)) {
+                        let response = self.elements.get_dyn_mut(index).gesture(gesture);
+                        self.respond(response);
+                    }
+                } else {
+                    let response = self.elements.get_dyn_mut(index).mouse_move(mouse);
+                    self.respond(response);
+                }
+            },
+            None => {
+                self.dispatch(|element| element.mouse_move(mouse));
+            },
+        }
+    }
+
+    /// Handle a pointer being interrupted, e.g. when a touch turns into a page scroll.
+    /// The captured element abandons whatever the pointer was doing.
+    pub fn pointer_cancel(&mut self, id: i32) {
+        let Some(pointer) = self.pointers.iter().find(|p| p.id == id).copied() else { return };
+        let in_gesture = pointer.capture.is_some_and(|index| self.gesture_pointers(index).is_some());
+        self.pointers.retain(|p| p.id != id);
+        if let Some(index) = pointer.capture {
+            if !in_gesture {
+                self.elements.get_dyn_mut(index).pointer_cancel(Mouse { x: pointer.x, y: pointer.y, down: false, id });
             }
         }
     }
 
-    pub fn mouse_move(&mut self, x: u32, y: u32, elements: Vec<*mut dyn Element>) {
-        let mouse = Mouse {
-            x,
-            y,
-            down: self.mouse_down
+    /// If element `index` is in the middle of a two-pointer gesture, return the two pointers
+    fn gesture_pointers(&self, index: usize) -> Option<(Pointer, Pointer)> {
+        if !self.elements.get_dyn(index).wants_gestures() {
+            return None;
+        }
+        let mut captured = self.pointers.iter().filter(|p| p.capture == Some(index));
+        match (captured.next(), captured.next()) {
+            (Some(a), Some(b)) => Some((*a, *b)),
+            _ => None,
+        }
+    }
+
+    /// Index of the element with keyboard focus
+    pub fn focused(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Give keyboard focus to an element. Elements which cannot take focus are ignored.
+    pub fn set_focus<T: Element>(&mut self, id: ElementId<T>) {
+        if self.elements.get(id).focusable() {
+            self.set_focus_index(Some(id.index()));
+        }
+    }
+
+    pub fn clear_focus(&mut self) {
+        self.set_focus_index(None);
+    }
+
+    /// Handle a key press. `key` is the `key` field of the DOM `KeyboardEvent` and `shift` its `shiftKey` field.
+    /// Returns true if the applet used the key, in which case the page should not.
+    pub fn key_down(&mut self, key: &str, shift: bool) -> bool {
+        let key = Key::from_name(key);
+        match key {
+            Key::Tab => self.cycle_focus(!shift),
+            Key::Escape if self.focus.is_some() => {
+                self.clear_focus();
+                true
+            },
+            _ => self.dispatch_focused(|element| element.key_down(key)),
+        }
+    }
+
+    /// Handle a key release. `key` is the `key` field of the DOM `KeyboardEvent`.
+    pub fn key_up(&mut self, key: &str) -> bool {
+        let key = Key::from_name(key);
+        self.dispatch_focused(|element| element.key_up(key))
+    }
+
+    /// Handle text typed by the user, e.g. from an `input` event
+    pub fn text_input(&mut self, text: &str) -> bool {
+        self.dispatch_focused(|element| element.text_input(text))
+    }
+
+    fn set_focus_index(&mut self, focus: Option<usize>) {
+        if focus == self.focus {
+            return;
+        }
+        if let Some(index) = self.focus {
+            self.elements.get_dyn_mut(index).set_focus(false);
+        }
+        if let Some(index) = focus {
+            self.elements.get_dyn_mut(index).set_focus(true);
+        }
+        self.focus = focus;
+    }
+
+    /// Move the focus to the next (or previous) focusable element. Returns false once focus leaves the applet,
+    /// so that tabbing can continue through the page.
+    fn cycle_focus(&mut self, forward: bool) -> bool {
+        let count = self.elements.len();
+        let mut candidates = (0..count).filter(|i| self.elements.get_dyn(*i).focusable()).collect::<Vec<_>>();
+        if !forward {
+            candidates.reverse();
+        }
+        let next = match self.focus {
+            None => candidates.first().copied(),
+            Some(current) => candidates.iter()
+                .position(|i| *i == current)
+                .and_then(|p| candidates.get(p + 1).copied()),
         };
-        for element in elements {
-            let element = unsafe { &mut *element };
-            match element.mouse_move(mouse) {
+        self.set_focus_index(next);
+        next.is_some()
+    }
+
+    /// Send an event to the focused element only
+    fn dispatch_focused(&mut self, event: impl FnOnce(&mut dyn Element) -> EventResponse) -> bool {
+        let Some(index) = self.focus else { return false };
+        let response = event(self.elements.get_dyn_mut(index));
+        self.respond(response)
+    }
+
+    /// Queue any callback in the response. Returns true if the element responded.
+    fn respond(&mut self, response: EventResponse) -> bool {
+        match response {
+            EventResponse::NoEvent => false,
+            EventResponse::Responded => true,
+            EventResponse::PlaceCallback(c) => {
+                self.callbacks.push(c);
+                true
+            },
+        }
+    }
+
+    /// Offer an event to each element in turn until one responds. Returns the index of the element which responded.
+    fn dispatch(&mut self, mut event: impl FnMut(&mut dyn Element) -> EventResponse) -> Option<usize> {
+        for (index, element) in self.elements.iter_mut().enumerate() {
+            match event(element.as_mut()) {
                 EventResponse::NoEvent => (),
-                EventResponse::Responded => return,
+                EventResponse::Responded => return Some(index),
                 EventResponse::PlaceCallback(c) => {
                     self.callbacks.push(c);
-                    return;
+                    return Some(index);
                 },
             }
         }
+        None
+    }
+}
+
+/// Work out the pinch and pan made by two pointers moving from `old` to `new`
+fn gestures_between(old: (Pointer, Pointer), new: (Pointer, Pointer)) -> Vec<Gesture> {
+    fn center(pair: (Pointer, Pointer)) -> (f32, f32) {
+        ((pair.0.x + pair.1.x) as f32 / 2., (pair.0.y + pair.1.y) as f32 / 2.)
     }
-}
\ No newline at end of file
+    fn separation(pair: (Pointer, Pointer)) -> f32 {
+        let dx = pair.0.x as f32 - pair.1.x as f32;
+        let dy = pair.0.y as f32 - pair.1.y as f32;
+        (dx*dx + dy*dy).sqrt()
+    }
+
+    let mut gestures = Vec::new();
+    let (old_center, new_center) = (center(old), center(new));
+    if old_center != new_center {
+        gestures.push(Gesture::Pan { dx: new_center.0 - old_center.0, dy: new_center.1 - old_center.1 });
+    }
+    let (old_separation, new_separation) = (separation(old), separation(new));
+    if old_separation > 0. && new_separation > 0. && old_separation != new_separation {
+        gestures.push(Gesture::Pinch { center: new_center, scale: new_separation / old_separation });
+    }
+    gestures
+}
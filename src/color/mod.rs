@@ -0,0 +1,237 @@
+use image::Rgba;
+
+mod names;
+mod parse;
+
+pub use parse::ColorParseError;
+
+/// A color with straight alpha. Channels run from 0 to 1, and the red, green and blue channels are sRGB encoded,
+/// as on screen and in `Rgba<u8>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// Space colors are interpolated in by `Color::lerp`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// The encoded channels, as most image software does
+    #[default]
+    Srgb,
+    /// Physical light intensity, which keeps mixes of bright colors bright
+    LinearRgb,
+    /// Hue, saturation and lightness. Hue takes the shorter way round.
+    Hsl,
+    /// Hue, saturation and value. Hue takes the shorter way round.
+    Hsv,
+    /// Perceptually even steps in lightness and hue
+    Oklab,
+}
+
+/// Decode one sRGB channel to linear light
+fn decode(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Encode one linear light channel as sRGB
+fn encode(c: f32) -> f32 {
+    if c <= 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1. / 2.4) - 0.055 }
+}
+
+/// Hue from `a` to `b` in degrees, going the shorter way round
+fn lerp_hue(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 540.) % 360. - 180.;
+    (a + t * delta).rem_euclid(360.)
+}
+
+impl Color {
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// An opaque color
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b, a: 1. }
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+
+    /// The color with its opacity multiplied by `opacity`
+    pub fn faded(self, opacity: f32) -> Self {
+        self.with_alpha(self.a * opacity.clamp(0., 1.))
+    }
+
+    /// Red, green, blue and alpha, with the colors multiplied by alpha
+    pub fn premultiplied(self) -> [f32; 4] {
+        [self.r * self.a, self.g * self.a, self.b * self.a, self.a]
+    }
+
+    /// The color of premultiplied channels. Fully transparent colors come back as transparent black.
+    pub fn from_premultiplied(p: [f32; 4]) -> Self {
+        if p[3] <= 0. {
+            return Self::rgba(0., 0., 0., 0.);
+        }
+        Self::rgba(p[0] / p[3], p[1] / p[3], p[2] / p[3], p[3])
+    }
+
+    /// The channels in linear light. The result is not sRGB encoded, so only `from_linear` should read it.
+    pub fn to_linear(self) -> Self {
+        Self::rgba(decode(self.r), decode(self.g), decode(self.b), self.a)
+    }
+
+    /// Encode channels given in linear light as sRGB
+    pub fn from_linear(self) -> Self {
+        Self::rgba(encode(self.r), encode(self.g), encode(self.b), self.a)
+    }
+
+    /// Composite this color over `below`, as paint on top of it
+    pub fn over(self, below: Color) -> Color {
+        let (src, dst) = (self.premultiplied(), below.premultiplied());
+        let keep = 1. - self.a.clamp(0., 1.);
+        Self::from_premultiplied([0, 1, 2, 3].map(|k| src[k] + dst[k] * keep))
+    }
+
+    /// Composite this color over `below` in linear light, which keeps antialiased edges and translucent overlaps
+    /// from looking too dark
+    pub fn over_linear(self, below: Color) -> Color {
+        self.to_linear().over(below.to_linear()).from_linear()
+    }
+
+    /// Hue in degrees from 0 to 360, saturation and lightness
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (max, min) = (self.r.max(self.g).max(self.b), self.r.min(self.g).min(self.b));
+        let lightness = (max + min) / 2.;
+        let chroma = max - min;
+        let saturation = if chroma == 0. { 0. } else { chroma / (1. - (2. * lightness - 1.).abs()) };
+        (self.hue(max, chroma), saturation, lightness)
+    }
+
+    /// An opaque color from hue in degrees, saturation and lightness
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+        Self::from_hue(hue, chroma, lightness - chroma / 2.)
+    }
+
+    /// Hue in degrees from 0 to 360, saturation and value
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (max, min) = (self.r.max(self.g).max(self.b), self.r.min(self.g).min(self.b));
+        let chroma = max - min;
+        let saturation = if max == 0. { 0. } else { chroma / max };
+        (self.hue(max, chroma), saturation, max)
+    }
+
+    /// An opaque color from hue in degrees, saturation and value
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        Self::from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Hue in degrees of a color whose largest channel is `max`, with `chroma` between its largest and smallest channels
+    fn hue(self, max: f32, chroma: f32) -> f32 {
+        if chroma == 0. {
+            return 0.;
+        }
+        let sector = if max == self.r {
+            ((self.g - self.b) / chroma).rem_euclid(6.)
+        } else if max == self.g {
+            (self.b - self.r) / chroma + 2.
+        } else {
+            (self.r - self.g) / chroma + 4.
+        };
+        60. * sector
+    }
+
+    /// An opaque color from hue in degrees, chroma and the value of its smallest channel
+    fn from_hue(hue: f32, chroma: f32, min: f32) -> Self {
+        let sector = hue.rem_euclid(360.) / 60.;
+        let x = chroma * (1. - (sector % 2. - 1.).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+        Self::rgb(r + min, g + min, b + min)
+    }
+
+    /// Lightness, green to red and blue to yellow in the OKLab space
+    pub fn to_oklab(self) -> (f32, f32, f32) {
+        let c = self.to_linear();
+        let l = (0.41222147 * c.r + 0.53633254 * c.g + 0.051445993 * c.b).cbrt();
+        let m = (0.2119035 * c.r + 0.6806995 * c.g + 0.10739696 * c.b).cbrt();
+        let s = (0.08830246 * c.r + 0.28171884 * c.g + 0.6299787 * c.b).cbrt();
+        (
+            0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+            1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+        )
+    }
+
+    /// An opaque color from OKLab lightness, green to red and blue to yellow. Colors outside sRGB are clipped to it.
+    pub fn from_oklab(lightness: f32, a: f32, b: f32) -> Self {
+        let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+        let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+        let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+        Self::rgb(
+            (4.0767417 * l - 3.3077116 * m + 0.23096993 * s).clamp(0., 1.),
+            (-1.268438 * l + 2.6097574 * m - 0.34131938 * s).clamp(0., 1.),
+            (-0.0041960863 * l - 0.7034186 * m + 1.7076147 * s).clamp(0., 1.),
+        ).from_linear()
+    }
+
+    /// The color a fraction `t` of the way from this color to `other`. Alpha is interpolated linearly, and in the
+    /// RGB and OKLab spaces the channels are weighted by it, so fading to a transparent color does not tint the mix.
+    pub fn lerp(self, other: Color, t: f32, space: ColorSpace) -> Color {
+        let a = self.a + t * (other.a - self.a);
+        let mix = |p: [f32; 3], q: [f32; 3]| [0, 1, 2].map(|k| p[k] + t * (q[k] - p[k]));
+        let weighted = |p: (f32, f32, f32), q: (f32, f32, f32)| {
+            let m = mix([p.0 * self.a, p.1 * self.a, p.2 * self.a], [q.0 * other.a, q.1 * other.a, q.2 * other.a]);
+            if a > 0. { (m[0] / a, m[1] / a, m[2] / a) } else { (0., 0., 0.) }
+        };
+        match space {
+            ColorSpace::Srgb => {
+                let (r, g, b) = weighted((self.r, self.g, self.b), (other.r, other.g, other.b));
+                Self::rgba(r, g, b, a)
+            },
+            ColorSpace::LinearRgb => {
+                let (p, q) = (self.to_linear(), other.to_linear());
+                let (r, g, b) = weighted((p.r, p.g, p.b), (q.r, q.g, q.b));
+                Self::rgba(r, g, b, a).from_linear()
+            },
+            ColorSpace::Oklab => {
+                let (l, ok_a, ok_b) = weighted(self.to_oklab(), other.to_oklab());
+                Self::from_oklab(l, ok_a, ok_b).with_alpha(a)
+            },
+            ColorSpace::Hsl | ColorSpace::Hsv => {
+                let hsl = space == ColorSpace::Hsl;
+                let (p, q) = if hsl { (self.to_hsl(), other.to_hsl()) } else { (self.to_hsv(), other.to_hsv()) };
+                // A grey has no hue of its own, so it takes the other color's
+                let (p_hue, q_hue) = (if p.1 == 0. { q.0 } else { p.0 }, if q.1 == 0. { p.0 } else { q.0 });
+                let m = mix([0., p.1, p.2], [0., q.1, q.2]);
+                let hue = lerp_hue(p_hue, q_hue, t);
+                let color = if hsl { Self::from_hsl(hue, m[1], m[2]) } else { Self::from_hsv(hue, m[1], m[2]) };
+                color.with_alpha(a)
+            },
+        }
+    }
+}
+
+impl From<Rgba<u8>> for Color {
+    fn from(color: Rgba<u8>) -> Self {
+        let [r, g, b, a] = color.0.map(|c| c as f32 / 255.);
+        Self::rgba(r, g, b, a)
+    }
+}
+
+impl From<Color> for Rgba<u8> {
+    fn from(color: Color) -> Self {
+        Rgba([color.r, color.g, color.b, color.a].map(|c| (c.clamp(0., 1.) * 255.).round() as u8))
+    }
+}
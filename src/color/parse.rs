@@ -0,0 +1,314 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::names::NAMED_COLORS;
+use super::{decode, Color};
+
+/// Why a string could not be read as a CSS color
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColorParseError {
+    /// A `#` not followed by 3, 4, 6 or 8 hex digits
+    InvalidHex(String),
+    /// A word which is not one of the CSS named colors
+    UnknownName(String),
+    /// A function which is not a CSS color function, or a `color()` space this parser does not know
+    UnknownFunction(String),
+    /// A color function with the wrong number or kind of arguments
+    InvalidArguments(String),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHex(s) => write!(f, "\"{}\" is not a hex color", s),
+            Self::UnknownName(s) => write!(f, "\"{}\" is not a named color", s),
+            Self::UnknownFunction(s) => write!(f, "\"{}\" is not a known color function", s),
+            Self::InvalidArguments(s) => write!(f, "\"{}\" has invalid arguments", s),
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+/// One argument of a color function
+#[derive(Clone, Copy, Debug)]
+enum Value {
+    Number(f32),
+    Percent(f32),
+    /// In degrees
+    Angle(f32),
+    /// The `none` keyword, a missing component which counts as zero
+    None,
+}
+
+impl Value {
+    fn parse(token: &str) -> Option<Value> {
+        if token == "none" {
+            return Some(Value::None);
+        }
+        let number = |s: &str| s.parse::<f32>().ok().filter(|x| x.is_finite());
+        if let Some(p) = token.strip_suffix('%') {
+            return number(p).map(Value::Percent);
+        }
+        for (unit, degrees) in [("deg", 1.), ("grad", 0.9), ("rad", 180. / std::f32::consts::PI), ("turn", 360.)] {
+            if let Some(a) = token.strip_suffix(unit) {
+                return number(a).map(|a| Value::Angle(a * degrees));
+            }
+        }
+        number(token).map(Value::Number)
+    }
+
+    /// A number, with a percentage scaled so that 100% is `full`
+    fn number(self, full: f32) -> Option<f32> {
+        match self {
+            Value::Number(x) => Some(x),
+            Value::Percent(p) => Some(p / 100. * full),
+            Value::None => Some(0.),
+            Value::Angle(_) => None,
+        }
+    }
+
+    /// A hue in degrees. Plain numbers are degrees.
+    fn hue(self) -> Option<f32> {
+        match self {
+            Value::Number(x) | Value::Angle(x) => Some(x),
+            Value::None => Some(0.),
+            Value::Percent(_) => None,
+        }
+    }
+}
+
+/// Multiply a matrix by a column of three values
+fn transform(m: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// CIE XYZ with a D65 white point to linear sRGB
+const XYZ_D65_TO_SRGB: [[f32; 3]; 3] = [
+    [3.24097, -1.5373832, -0.49861076],
+    [-0.96924365, 1.8759675, 0.04155506],
+    [0.05563008, -0.20397696, 1.0569715],
+];
+/// CIE XYZ with a D50 white point, which Lab and ProPhoto use, to linear sRGB
+const XYZ_D50_TO_SRGB: [[f32; 3]; 3] = [
+    [3.134136, -1.6173863, -0.49066195],
+    [-0.9787955, 1.9162546, 0.033442732],
+    [0.07195538, -0.22897682, 1.405386],
+];
+const DISPLAY_P3_TO_SRGB: [[f32; 3]; 3] = [
+    [1.2249401, -0.2249404, 0.],
+    [-0.0420569, 1.0420571, 0.],
+    [-0.0196376, -0.0786361, 1.0982735],
+];
+const A98_RGB_TO_SRGB: [[f32; 3]; 3] = [
+    [1.3982832, -0.3982831, 0.],
+    [0., 1., 0.],
+    [0., -0.0429383, 1.0429383],
+];
+const REC2020_TO_SRGB: [[f32; 3]; 3] = [
+    [1.660491, -0.5876411, -0.0728499],
+    [-0.1245505, 1.1328999, -0.0083494],
+    [-0.0181508, -0.1005789, 1.1187297],
+];
+const PROPHOTO_TO_XYZ_D50: [[f32; 3]; 3] = [
+    [0.7977605, 0.1351858, 0.0313493],
+    [0.2880711, 0.7118432, 0.0000857],
+    [0., 0., 0.8251046],
+];
+
+/// An opaque color from channels in linear sRGB. Colors outside sRGB are clipped to it.
+fn from_linear_srgb(c: [f32; 3]) -> Color {
+    let [r, g, b] = c.map(|c| c.clamp(0., 1.));
+    Color::rgb(r, g, b).from_linear()
+}
+
+/// An opaque color from CIE Lab, lightness from 0 to 100
+fn from_lab(lightness: f32, a: f32, b: f32) -> Color {
+    const KAPPA: f32 = 24389. / 27.;
+    const EPSILON: f32 = 216. / 24389.;
+    const WHITE: [f32; 3] = [0.3457 / 0.3585, 1., (1. - 0.3457 - 0.3585) / 0.3585];
+    let fy = (lightness + 16.) / 116.;
+    let fx = fy + a / 500.;
+    let fz = fy - b / 200.;
+    let f_inv = |f: f32| if f.powi(3) > EPSILON { f.powi(3) } else { (116. * f - 16.) / KAPPA };
+    let y = if lightness > KAPPA * EPSILON { fy.powi(3) } else { lightness / KAPPA };
+    let xyz = [f_inv(fx) * WHITE[0], y * WHITE[1], f_inv(fz) * WHITE[2]];
+    from_linear_srgb(transform(XYZ_D50_TO_SRGB, xyz))
+}
+
+/// Read `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+fn parse_hex(s: &str, digits: &str) -> Result<Color, ColorParseError> {
+    let error = || ColorParseError::InvalidHex(s.to_owned());
+    if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error());
+    }
+    let channel = |i: usize, width: usize| {
+        let c = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+        if width == 1 { c * 17 } else { c }
+    };
+    let (count, width) = match digits.len() {
+        3 => (3, 1),
+        4 => (4, 1),
+        6 => (3, 2),
+        8 => (4, 2),
+        _ => return Err(error()),
+    };
+    let alpha = if count == 4 { channel(3, width) } else { 255 };
+    Ok(image::Rgba([channel(0, width), channel(1, width), channel(2, width), alpha]).into())
+}
+
+/// Split the arguments of a color function into its components and alpha. Both the comma separated legacy syntax
+/// and the space separated syntax with an optional `/ alpha` are accepted.
+fn split_arguments(args: &str, legacy: bool) -> Option<(Vec<Value>, Option<Value>)> {
+    let (components, alpha) = if legacy && args.contains(',') {
+        let mut values = args.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+        let alpha = if values.len() == 4 { values.pop() } else { None };
+        (values, alpha)
+    } else {
+        let mut parts = args.splitn(2, '/');
+        let components = parts.next()?.split_whitespace().collect::<Vec<_>>();
+        let alpha = match parts.next() {
+            Some(a) => match a.split_whitespace().collect::<Vec<_>>()[..] {
+                [a] => Some(a),
+                _ => return None,
+            },
+            None => None,
+        };
+        (components, alpha)
+    };
+    let components = components.into_iter().map(Value::parse).collect::<Option<Vec<_>>>()?;
+    let alpha = match alpha {
+        Some(a) => Some(Value::parse(a)?),
+        None => None,
+    };
+    Some((components, alpha))
+}
+
+/// Read a color function such as `rgb(255 0 0 / 50%)`, given its name and the text between the brackets
+fn parse_function(s: &str, name: &str, args: &str) -> Result<Color, ColorParseError> {
+    let error = || ColorParseError::InvalidArguments(s.to_owned());
+    const FUNCTIONS: [&str; 10] = ["rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch", "color"];
+    if !FUNCTIONS.contains(&name) {
+        return Err(ColorParseError::UnknownFunction(s.to_owned()));
+    }
+    // The color space of color() comes before its components
+    let (space, args) = if name == "color" {
+        let end = args.find(char::is_whitespace).unwrap_or(args.len());
+        (&args[..end], &args[end..])
+    } else {
+        ("", args)
+    };
+    let legacy = matches!(name, "rgb" | "rgba" | "hsl" | "hsla");
+    let (components, alpha) = split_arguments(args, legacy).ok_or_else(error)?;
+    let alpha = match alpha {
+        Some(a) => a.number(1.).ok_or_else(error)?.clamp(0., 1.),
+        None => 1.,
+    };
+    let [x, y, z] = <[Value; 3]>::try_from(components).map_err(|_| error())?;
+
+    let color = match name {
+        "rgb" | "rgba" => {
+            let [r, g, b] = [x, y, z].map(|v| v.number(255.).map(|c| (c / 255.).clamp(0., 1.)));
+            Color::rgb(r.ok_or_else(error)?, g.ok_or_else(error)?, b.ok_or_else(error)?)
+        },
+        "hsl" | "hsla" => {
+            let hue = x.hue().ok_or_else(error)?;
+            let saturation = y.number(100.).ok_or_else(error)? / 100.;
+            let lightness = z.number(100.).ok_or_else(error)? / 100.;
+            Color::from_hsl(hue, saturation.clamp(0., 1.), lightness.clamp(0., 1.))
+        },
+        "hwb" => {
+            let hue = x.hue().ok_or_else(error)?;
+            let white = y.number(100.).ok_or_else(error)?.clamp(0., 100.) / 100.;
+            let black = z.number(100.).ok_or_else(error)?.clamp(0., 100.) / 100.;
+            if white + black >= 1. {
+                let grey = white / (white + black);
+                Color::rgb(grey, grey, grey)
+            } else {
+                let value = 1. - black;
+                Color::from_hsv(hue, 1. - white / value, value)
+            }
+        },
+        "lab" => {
+            let lightness = x.number(100.).ok_or_else(error)?.clamp(0., 100.);
+            from_lab(lightness, y.number(125.).ok_or_else(error)?, z.number(125.).ok_or_else(error)?)
+        },
+        "lch" => {
+            let lightness = x.number(100.).ok_or_else(error)?.clamp(0., 100.);
+            let chroma = y.number(150.).ok_or_else(error)?.max(0.);
+            let hue = z.hue().ok_or_else(error)?.to_radians();
+            from_lab(lightness, chroma * hue.cos(), chroma * hue.sin())
+        },
+        "oklab" => {
+            let lightness = x.number(1.).ok_or_else(error)?.clamp(0., 1.);
+            Color::from_oklab(lightness, y.number(0.4).ok_or_else(error)?, z.number(0.4).ok_or_else(error)?)
+        },
+        "oklch" => {
+            let lightness = x.number(1.).ok_or_else(error)?.clamp(0., 1.);
+            let chroma = y.number(0.4).ok_or_else(error)?.max(0.);
+            let hue = z.hue().ok_or_else(error)?.to_radians();
+            Color::from_oklab(lightness, chroma * hue.cos(), chroma * hue.sin())
+        },
+        "color" => {
+            let c = [x, y, z].map(|v| v.number(1.));
+            let c = [c[0].ok_or_else(error)?, c[1].ok_or_else(error)?, c[2].ok_or_else(error)?];
+            match space {
+                "srgb" => {
+                    let [r, g, b] = c.map(|c| c.clamp(0., 1.));
+                    Color::rgb(r, g, b)
+                },
+                "srgb-linear" => from_linear_srgb(c),
+                "display-p3" => from_linear_srgb(transform(DISPLAY_P3_TO_SRGB, c.map(decode))),
+                "a98-rgb" => {
+                    let linear = c.map(|c| c.signum() * c.abs().powf(563. / 256.));
+                    from_linear_srgb(transform(A98_RGB_TO_SRGB, linear))
+                },
+                "rec2020" => {
+                    const ALPHA: f32 = 1.0992968;
+                    const BETA: f32 = 0.01805397;
+                    let decode = |c: f32| {
+                        if c.abs() < BETA * 4.5 { c / 4.5 } else { c.signum() * ((c.abs() + ALPHA - 1.) / ALPHA).powf(1. / 0.45) }
+                    };
+                    from_linear_srgb(transform(REC2020_TO_SRGB, c.map(decode)))
+                },
+                "prophoto-rgb" => {
+                    let decode = |c: f32| if c.abs() <= 16. / 512. { c / 16. } else { c.signum() * c.abs().powf(1.8) };
+                    from_linear_srgb(transform(XYZ_D50_TO_SRGB, transform(PROPHOTO_TO_XYZ_D50, c.map(decode))))
+                },
+                "xyz" | "xyz-d65" => from_linear_srgb(transform(XYZ_D65_TO_SRGB, c)),
+                "xyz-d50" => from_linear_srgb(transform(XYZ_D50_TO_SRGB, c)),
+                _ => return Err(ColorParseError::UnknownFunction(s.to_owned())),
+            }
+        },
+        _ => return Err(ColorParseError::UnknownFunction(s.to_owned())),
+    };
+    Ok(color.with_alpha(alpha))
+}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    /// Read a CSS color: a hex color, a named color, `transparent`, or one of the functions `rgb()`, `rgba()`,
+    /// `hsl()`, `hsla()`, `hwb()`, `lab()`, `lch()`, `oklab()`, `oklch()` and `color()`. Case and surrounding
+    /// space are ignored, and colors outside sRGB are clipped to it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        if let Some(digits) = lower.strip_prefix('#') {
+            return parse_hex(s, digits);
+        }
+        if let Some((name, rest)) = lower.split_once('(') {
+            let args = rest.strip_suffix(')').ok_or_else(|| ColorParseError::InvalidArguments(s.to_owned()))?;
+            return parse_function(s, name.trim_end(), args.trim());
+        }
+        if lower == "transparent" {
+            return Ok(Color::rgba(0., 0., 0., 0.));
+        }
+        match NAMED_COLORS.binary_search_by_key(&lower.as_str(), |(name, _)| name) {
+            Ok(i) => {
+                let [r, g, b] = NAMED_COLORS[i].1;
+                Ok(image::Rgba([r, g, b, 255]).into())
+            },
+            Err(_) => Err(ColorParseError::UnknownName(s.to_owned())),
+        }
+    }
+}
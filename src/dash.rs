@@ -0,0 +1,109 @@
+/// How the ends of a line are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CapStyle {
+    /// The line stops exactly at its end points
+    Butt,
+    /// The line ends in a half circle
+    #[default]
+    Round,
+}
+
+/// Patterns which repeat in less than this many pixels are drawn solid, as their dashes could not be seen
+const MIN_PERIOD: f32 = 0.5;
+/// Most dashes one segment is split into. Longer segments, which lie far outside any image, are drawn solid.
+const MAX_DASHES_PER_SEGMENT: f32 = 1e5;
+
+/// Splits polylines into dashes. The dash phase carries over from one segment to the next, and from one call
+/// to `dash` to the next, so a line broken into several pieces keeps a regular pattern.
+pub struct Dasher {
+    /// Alternating on and off lengths in pixels. Empty for a solid line.
+    pattern: Vec<f32>,
+    index: usize,
+    remaining: f32,
+}
+
+impl Dasher {
+    /// `pattern` lists alternating on and off lengths in pixels, starting with an on length.
+    /// Patterns with an odd number of entries are repeated, as in SVG. Empty or degenerate patterns, and patterns
+    /// which repeat in less than half a pixel, give a solid line.
+    pub fn new(pattern: &[f32]) -> Self {
+        let mut pattern = pattern.to_vec();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_within(..);
+        }
+        let valid = pattern.iter().all(|l| l.is_finite() && *l >= 0.) && pattern.iter().sum::<f32>() >= MIN_PERIOD;
+        if !valid {
+            pattern.clear();
+        }
+        let remaining = pattern.first().copied().unwrap_or(0.);
+        Self {
+            pattern,
+            index: 0,
+            remaining,
+        }
+    }
+
+    /// Returns true if the dasher is in an on part of the pattern
+    fn on(&self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+
+    /// Length in pixels after which the pattern repeats
+    fn period(&self) -> f32 {
+        self.pattern.iter().sum()
+    }
+
+    fn advance(&mut self) {
+        self.index = (self.index + 1) % self.pattern.len();
+        self.remaining = self.pattern[self.index];
+    }
+
+    /// Split a polyline into the pieces which should be drawn. A zero-length dash gives a piece with two equal points,
+    /// which draws as a dot with round caps.
+    pub fn dash(&mut self, points: &[(f32, f32)]) -> Vec<Vec<(f32, f32)>> {
+        if points.is_empty() {
+            return Vec::new();
+        }
+        if self.pattern.is_empty() {
+            return vec![points.to_vec()];
+        }
+
+        let mut pieces = Vec::new();
+        let mut current = if self.on() { Some(vec![points[0]]) } else { None };
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+            if length == 0. {
+                continue;
+            }
+            if length.is_nan() || length / self.period() > MAX_DASHES_PER_SEGMENT {
+                current.get_or_insert_with(|| vec![a]).push(b);
+                continue;
+            }
+            let mut pos = 0.;
+            while length - pos >= self.remaining {
+                pos += self.remaining;
+                let t = pos / length;
+                let p = (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+                match current.take() {
+                    Some(mut piece) => {
+                        piece.push(p);
+                        pieces.push(piece);
+                    },
+                    None => current = Some(vec![p]),
+                }
+                self.advance();
+            }
+            self.remaining -= length - pos;
+            if let Some(piece) = current.as_mut() {
+                piece.push(b);
+            }
+        }
+        if let Some(piece) = current {
+            if piece.len() >= 2 {
+                pieces.push(piece);
+            }
+        }
+        pieces
+    }
+}
@@ -1,8 +1,10 @@
 use image::Rgba;
 
-use crate::{style::TextAlign, Callback, Dim, Style};
+use crate::{style::{TextAlign, WHITE}, Callback, Color, ColorSpace, Dim, Painter, Style};
 
-use super::{Element, EventResponse, Mouse};
+use super::{Element, ElementId, EventResponse, Key, Mouse};
+
+const FOCUS_INSET: u32 = 4;
 
 pub struct Button {
     top: u32,
@@ -11,7 +13,9 @@ pub struct Button {
     right: u32,
     text: String,
     
+    id: Option<ElementId<Button>>,
     hover: bool,
+    focused: bool,
 }
 
 impl Button {
@@ -27,41 +31,42 @@ impl Button {
             right: left + width,
             text,
 
+            id: None,
             hover: false,
+            focused: false,
+        }
+    }
+
+    /// A click places a callback, so a button which was never added to an applet has nothing to respond with
+    fn click(&self) -> EventResponse {
+        match self.id {
+            Some(id) => EventResponse::PlaceCallback(Callback::ButtonClicked(id)),
+            None => EventResponse::NoEvent,
         }
     }
 }
 
 impl Element for Button {
-    fn draw(&self, pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, style: &Style) {
-        let light_color = Rgba([
-            255 - (255 - style.highlight_color.0[0])/4,
-            255 - (255 - style.highlight_color.0[1])/4,
-            255 - (255 - style.highlight_color.0[2])/4,
-            255,
-        ]);
+    fn attach(&mut self, id: ElementId<Self>) {
+        self.id = Some(id);
+    }
+
+    fn draw(&self, painter: &mut Painter, style: &Style) {
+        let light_color = Color::from(style.highlight_color).lerp(Color::from(WHITE), 0.75, ColorSpace::Srgb).into();
+        let (left, top, right, bottom) = (self.left as f32, self.top as f32, self.right as f32, self.bottom as f32);
         if self.hover {
-            for i in 0..self.right - self.left {
-                for j in 0..self.bottom - self.top {
-                    pixels[(self.left + i, self.top + j)] = light_color;
-                }
-            }
-        }
-        for i in 0..self.right - self.left {
-            pixels[(self.left + i, self.top)] = style.highlight_color;
-            pixels[(self.left + i, self.top+1)] = style.highlight_color;
-            pixels[(self.left + i, self.bottom)] = style.highlight_color;
-            pixels[(self.left + i, self.bottom-1)] = style.highlight_color;
+            painter.fill_rect((left - 0.5, top - 0.5, right - 0.5, bottom - 0.5), light_color);
         }
-        for j in 0..self.bottom - self.top {
-            pixels[(self.left, self.top + j)] = style.highlight_color;
-            pixels[(self.left+1, self.top + j)] = style.highlight_color;
-            pixels[(self.right, self.top + j)] = style.highlight_color;
-            pixels[(self.right-1, self.top + j)] = style.highlight_color;
+        // Two pixels wide, covering the outermost pixels
+        painter.draw_rect((left + 0.5, top + 0.5, right - 0.5, bottom - 0.5), 2., style.highlight_color);
+        if self.focused {
+            // Inner outline to show keyboard focus
+            let inset = FOCUS_INSET as f32;
+            painter.draw_rect((left + inset, top + inset, right - inset, bottom - inset), 1., style.highlight_color);
         }
 
-        style.render_text(
-            pixels, (self.left + self.right) / 2, (self.top + self.bottom) / 2,
+        painter.draw_text(
+            style, (((self.left + self.right) / 2) as i32, ((self.top + self.bottom) / 2) as i32),
             &self.text, Rgba([0,0,0,255]), TextAlign::Center, TextAlign::Center
         );
     }
@@ -75,7 +80,7 @@ impl Element for Button {
 
     fn mouse_button_up(&mut self, mouse: Mouse) -> EventResponse {
         if !self.bbox(mouse) { return EventResponse::NoEvent }
-        EventResponse::PlaceCallback(Callback::ButtonClicked(self as *const Self))
+        self.click()
     }
 
     fn mouse_move(&mut self, mouse: Mouse) -> EventResponse {
@@ -84,4 +89,28 @@ impl Element for Button {
         self.hover = true;
         EventResponse::Responded
     }
-}
\ No newline at end of file
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn key_down(&mut self, key: Key) -> EventResponse {
+        match key {
+            Key::Enter => self.click(),
+            // Space clicks when it is released, as buttons on the page do, so holding it down does not repeat
+            Key::Space => EventResponse::Responded,
+            _ => EventResponse::NoEvent,
+        }
+    }
+
+    fn key_up(&mut self, key: Key) -> EventResponse {
+        match key {
+            Key::Space => self.click(),
+            _ => EventResponse::NoEvent,
+        }
+    }
+}
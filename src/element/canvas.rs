@@ -0,0 +1,321 @@
+use image::Rgba;
+
+use crate::{style::{TextAlign, BLACK}, Callback, CapStyle, Dim, Painter, Style};
+
+use super::{Element, ElementId, EventResponse, Mouse};
+
+/// Distance in pixels from a shape within which the pointer still picks it
+const HIT_TOLERANCE: f32 = 4.;
+
+/// How world coordinates map onto the pixels of a `CanvasElement`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// World position shown at the middle of the canvas
+    pub center: (f32, f32),
+    /// Pixels per world unit
+    pub scale: f32,
+    /// Turn of the world about the center in radians, counterclockwise on screen
+    pub rotation: f32,
+    /// World y increases upwards, as in most physics, rather than downwards as pixels do
+    pub y_up: bool,
+}
+
+impl Transform {
+    /// Position in pixels from the upper left corner of a canvas of the given size
+    pub fn world_to_pixel(&self, pos: (f32, f32), size: (u32, u32)) -> (f32, f32) {
+        let flip = if self.y_up { -1. } else { 1. };
+        let d = ((pos.0 - self.center.0) * self.scale, (pos.1 - self.center.1) * self.scale * flip);
+        let (sin, cos) = self.rotation.sin_cos();
+        // Pixels run downwards, so turning counterclockwise on screen takes x towards -y
+        (
+            size.0 as f32 / 2. + d.0 * cos + d.1 * sin,
+            size.1 as f32 / 2. - d.0 * sin + d.1 * cos,
+        )
+    }
+
+    /// World position of a pixel position in a canvas of the given size
+    pub fn pixel_to_world(&self, pixel: (f32, f32), size: (u32, u32)) -> (f32, f32) {
+        let flip = if self.y_up { -1. } else { 1. };
+        let p = (pixel.0 - size.0 as f32 / 2., pixel.1 - size.1 as f32 / 2.);
+        let (sin, cos) = self.rotation.sin_cos();
+        let d = (p.0 * cos - p.1 * sin, p.0 * sin + p.1 * cos);
+        (self.center.0 + d.0 / self.scale, self.center.1 + d.1 * flip / self.scale)
+    }
+}
+
+/// Colors and line width of a `Shape`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeStyle {
+    /// Inside color of circles and polygons
+    pub fill: Option<Rgba<u8>>,
+    /// Color of the outline, or of the whole shape for lines and arrows
+    pub stroke: Option<Rgba<u8>>,
+    /// Outline width in pixels
+    pub line_width: f32,
+}
+
+impl Default for ShapeStyle {
+    fn default() -> Self {
+        Self {
+            fill: None,
+            stroke: Some(BLACK),
+            line_width: 1.5,
+        }
+    }
+}
+
+/// Something drawn by a `CanvasElement`. Positions and the radius of circles are in world units.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle{center: (f32, f32), radius: f32, style: ShapeStyle},
+    /// A line through points
+    Line{points: Vec<(f32, f32)>, style: ShapeStyle},
+    /// A line with a filled head at `to`, `head_size` pixels long
+    Arrow{from: (f32, f32), to: (f32, f32), head_size: f32, style: ShapeStyle},
+    Polygon{points: Vec<(f32, f32)>, style: ShapeStyle},
+    /// Text in the applet's font, aligned to `pos`. Text is not picked by the pointer.
+    Text{pos: (f32, f32), text: String, color: Rgba<u8>, va: TextAlign, ha: TextAlign},
+}
+
+/// Distance in pixels from p to the segment from a to b
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let v = (b.0 - a.0, b.1 - a.1);
+    let length2 = v.0 * v.0 + v.1 * v.1;
+    let t = if length2 > 0. { (((p.0 - a.0) * v.0 + (p.1 - a.1) * v.1) / length2).clamp(0., 1.) } else { 0. };
+    (p.0 - a.0 - t * v.0).hypot(p.1 - a.1 - t * v.1)
+}
+
+/// True if the polygon winds around p
+fn winds_around(points: &[(f32, f32)], p: (f32, f32)) -> bool {
+    let mut winding = 0;
+    for (k, a) in points.iter().enumerate() {
+        let b = points[(k + 1) % points.len()];
+        let side = (b.0 - a.0) * (p.1 - a.1) - (p.0 - a.0) * (b.1 - a.1);
+        if a.1 <= p.1 && b.1 > p.1 && side > 0. {
+            winding += 1;
+        } else if a.1 > p.1 && b.1 <= p.1 && side < 0. {
+            winding -= 1;
+        }
+    }
+    winding != 0
+}
+
+/// Corners in pixels of the head of an arrow pointing from `from` to `to`
+fn arrow_head(from: (f32, f32), to: (f32, f32), head_size: f32) -> [(f32, f32); 3] {
+    let length = (to.0 - from.0).hypot(to.1 - from.1).max(f32::EPSILON);
+    let along = ((to.0 - from.0) / length, (to.1 - from.1) / length);
+    let base = (to.0 - along.0 * head_size, to.1 - along.1 * head_size);
+    let half = head_size / 3.;
+    [to, (base.0 - along.1 * half, base.1 + along.0 * half), (base.0 + along.1 * half, base.1 - along.0 * half)]
+}
+
+/// Draws a scene of shapes given in world coordinates, for simulations and other custom visuals.
+/// Replace the shapes whenever the scene changes, for example on every tick.
+pub struct CanvasElement {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    transform: Transform,
+    shapes: Vec<Shape>,
+    background: Option<Rgba<u8>>,
+
+    id: Option<ElementId<CanvasElement>>,
+    /// Index of the shape under the mouse
+    hover: Option<usize>,
+}
+
+impl CanvasElement {
+    /// A canvas covering `rect` (left, top, width, height). World coordinates start out as pixels from its upper left corner.
+    pub fn new(rect: (Dim, Dim, Dim, Dim), window_width: u32, window_height: u32) -> Self {
+        let width = rect.2.to_pixel(window_width, window_height, true);
+        let height = rect.3.to_pixel(window_width, window_height, false);
+        Self {
+            left: rect.0.to_pixel(window_width, window_height, true),
+            top: rect.1.to_pixel(window_width, window_height, false),
+            width,
+            height,
+            transform: Transform {
+                center: (width as f32 / 2., height as f32 / 2.),
+                scale: 1.,
+                rotation: 0.,
+                y_up: false,
+            },
+            shapes: Vec::new(),
+            background: None,
+
+            id: None,
+            hover: None,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    /// Color to fill the canvas with before drawing, or None to draw over whatever is behind it
+    pub fn set_background(&mut self, background: Option<Rgba<u8>>) {
+        self.background = background;
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// Replace every shape. Shapes are drawn in order, so later shapes cover earlier ones.
+    pub fn set_shapes(&mut self, shapes: Vec<Shape>) {
+        self.shapes = shapes;
+        self.hover = None;
+    }
+
+    pub fn push(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.hover = None;
+    }
+
+    /// Window pixel position of a world position
+    pub fn world_to_pixel(&self, pos: (f32, f32)) -> (f32, f32) {
+        let (x, y) = self.transform.world_to_pixel(pos, (self.width, self.height));
+        (x + self.left as f32, y + self.top as f32)
+    }
+
+    /// World position of a window pixel position
+    pub fn pixel_to_world(&self, pixel: (f32, f32)) -> (f32, f32) {
+        self.transform.pixel_to_world((pixel.0 - self.left as f32, pixel.1 - self.top as f32), (self.width, self.height))
+    }
+
+    /// Index of the topmost shape at a window pixel position, if any. Shapes count as solid, and lines and outlines
+    /// are picked from a few pixels away.
+    pub fn hit_test(&self, pixel: (f32, f32)) -> Option<usize> {
+        let p = (pixel.0 - self.left as f32, pixel.1 - self.top as f32);
+        if p.0 < 0. || p.1 < 0. || p.0 >= self.width as f32 || p.1 >= self.height as f32 {
+            return None;
+        }
+        let to_pixel = |pos: (f32, f32)| self.transform.world_to_pixel(pos, (self.width, self.height));
+        let near = |points: &[(f32, f32)], style: &ShapeStyle| {
+            points.windows(2).any(|pair| segment_distance(p, pair[0], pair[1]) <= style.line_width / 2. + HIT_TOLERANCE)
+        };
+        self.shapes.iter().rposition(|shape| match shape {
+            Shape::Circle { center, radius, style } => {
+                let c = to_pixel(*center);
+                (p.0 - c.0).hypot(p.1 - c.1) <= radius * self.transform.scale + style.line_width / 2. + HIT_TOLERANCE
+            },
+            Shape::Line { points, style } => near(&points.iter().map(|q| to_pixel(*q)).collect::<Vec<_>>(), style),
+            Shape::Arrow { from, to, head_size, style } => {
+                let (a, b) = (to_pixel(*from), to_pixel(*to));
+                near(&[a, b], style) || winds_around(&arrow_head(a, b, *head_size), p)
+            },
+            Shape::Polygon { points, style } => {
+                let mut points = points.iter().map(|q| to_pixel(*q)).collect::<Vec<_>>();
+                let inside = points.len() >= 3 && winds_around(&points, p);
+                points.extend(points.first().copied());
+                inside || near(&points, style)
+            },
+            Shape::Text { .. } => false,
+        })
+    }
+
+    /// Index of the shape under the mouse, if any
+    pub fn hovered_shape(&self) -> Option<usize> {
+        self.hover
+    }
+
+    fn draw_shape(&self, painter: &mut Painter, shape: &Shape, style: &Style) {
+        let to_pixel = |pos: (f32, f32)| self.world_to_pixel(pos);
+        match shape {
+            Shape::Circle { center, radius, style } => {
+                let (c, r) = (to_pixel(*center), radius * self.transform.scale);
+                if let Some(fill) = style.fill {
+                    painter.fill_circle(c, r, fill);
+                }
+                if let Some(stroke) = style.stroke {
+                    painter.draw_circle(c, r, style.line_width, stroke);
+                }
+            },
+            Shape::Line { points, style } => {
+                let Some(stroke) = style.stroke else { return };
+                let points = points.iter().map(|p| to_pixel(*p)).collect::<Vec<_>>();
+                painter.draw_polyline(&points, style.line_width, CapStyle::Round, stroke);
+            },
+            Shape::Arrow { from, to, head_size, style } => {
+                let Some(stroke) = style.stroke else { return };
+                let (a, b) = (to_pixel(*from), to_pixel(*to));
+                let head = arrow_head(a, b, *head_size);
+                // The shaft stops inside the head, so that its end does not poke out of the tip
+                let length = (b.0 - a.0).hypot(b.1 - a.1);
+                let t = if length > 0. { (1. - head_size / 2. / length).max(0.) } else { 0. };
+                let end = (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+                painter.draw_line(a, end, style.line_width, (CapStyle::Round, CapStyle::Butt), stroke);
+                painter.fill_polygon(&head, stroke);
+            },
+            Shape::Polygon { points, style } => {
+                let mut points = points.iter().map(|p| to_pixel(*p)).collect::<Vec<_>>();
+                if let Some(fill) = style.fill {
+                    painter.fill_polygon(&points, fill);
+                }
+                if let Some(stroke) = style.stroke {
+                    points.extend(points.first().copied());
+                    painter.draw_polyline(&points, style.line_width, CapStyle::Round, stroke);
+                }
+            },
+            Shape::Text { pos, text, color, va, ha } => {
+                let (x, y) = to_pixel(*pos);
+                painter.draw_text(style, (x.round() as i32, y.round() as i32), text, *color, *va, *ha);
+            },
+        }
+    }
+}
+
+impl Element for CanvasElement {
+    fn attach(&mut self, id: ElementId<Self>) {
+        self.id = Some(id);
+    }
+
+    fn draw(&self, painter: &mut Painter, style: &Style) {
+        // Shapes are cut off at the edges of the canvas, and at any clip the canvas is drawn inside
+        let clip = painter.clip();
+        painter.set_clip((
+            self.left.max(clip.0),
+            self.top.max(clip.1),
+            (self.left + self.width).min(clip.2),
+            (self.top + self.height).min(clip.3),
+        ));
+        if let Some(background) = self.background {
+            painter.fill(background);
+        }
+        for shape in &self.shapes {
+            self.draw_shape(painter, shape, style);
+        }
+        painter.set_clip(clip);
+    }
+
+    fn bbox(&self, mouse: Mouse) -> bool {
+        mouse.x >= self.left &&
+        mouse.x < self.left + self.width &&
+        mouse.y >= self.top &&
+        mouse.y < self.top + self.height
+    }
+
+    /// Pressing on a shape places a callback, so that the applet can start dragging it
+    fn mouse_button_down(&mut self, mouse: Mouse) -> EventResponse {
+        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        match (self.id, self.hit_test((mouse.x as f32, mouse.y as f32))) {
+            (Some(id), Some(shape)) => EventResponse::PlaceCallback(Callback::ShapePressed(id, shape)),
+            _ => EventResponse::Responded,
+        }
+    }
+
+    fn mouse_move(&mut self, mouse: Mouse) -> EventResponse {
+        self.hover = self.hit_test((mouse.x as f32, mouse.y as f32));
+        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        EventResponse::Responded
+    }
+}
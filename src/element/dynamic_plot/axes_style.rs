@@ -0,0 +1,81 @@
+use image::Rgba;
+
+use crate::Color;
+
+use super::LineStyle;
+
+/// Which sides of the axes get a line
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Spines {
+    /// Left and bottom
+    #[default]
+    Open,
+    /// All four sides
+    Box,
+    None,
+}
+
+/// Which way tick marks point from their axis
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TickDirection {
+    #[default]
+    In,
+    Out,
+}
+
+/// Lines across the axes at the tick positions
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridStyle {
+    pub color: Rgba<u8>,
+    /// Opacity between 0 and 1
+    pub alpha: f32,
+    pub ls: LineStyle,
+    /// Line width in pixels
+    pub line_width: f32,
+}
+
+impl Default for GridStyle {
+    fn default() -> Self {
+        Self {
+            color: Rgba([176, 176, 176, 255]),
+            alpha: 1.,
+            ls: LineStyle::Solid,
+            line_width: 1.,
+        }
+    }
+}
+
+impl GridStyle {
+    /// Color with the opacity folded in
+    pub(super) fn blended_color(&self) -> Rgba<u8> {
+        Color::from(self.color).faded(self.alpha).into()
+    }
+}
+
+/// Figure options for the axes themselves, set with `PlotCommand::SetAxesStyle`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxesStyle {
+    pub spines: Spines,
+    pub tick_direction: TickDirection,
+    /// Draw the x axis' ticks, tick labels and label
+    pub show_x: bool,
+    /// Draw the y axis' ticks, tick labels and label
+    pub show_y: bool,
+    /// Grid lines at the major ticks
+    pub major_grid: Option<GridStyle>,
+    /// Grid lines at the minor ticks
+    pub minor_grid: Option<GridStyle>,
+}
+
+impl Default for AxesStyle {
+    fn default() -> Self {
+        Self {
+            spines: Spines::Open,
+            tick_direction: TickDirection::In,
+            show_x: true,
+            show_y: true,
+            major_grid: None,
+            minor_grid: None,
+        }
+    }
+}
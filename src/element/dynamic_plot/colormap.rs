@@ -0,0 +1,83 @@
+use image::Rgba;
+
+/// Maps numbers between 0 and 1 to colors. The colors follow matplotlib's colormaps of the same names.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Colormap {
+    #[default]
+    Viridis,
+    Magma,
+    /// Diverging from blue through grey to red, for data either side of a center value
+    Coolwarm,
+    /// White to black
+    Greys,
+}
+
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [28, 16, 68],
+    [79, 18, 123],
+    [129, 37, 129],
+    [181, 54, 122],
+    [229, 80, 100],
+    [251, 135, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+const COOLWARM: [[u8; 3]; 9] = [
+    [59, 76, 192],
+    [98, 130, 234],
+    [141, 176, 254],
+    [184, 208, 249],
+    [221, 221, 221],
+    [245, 196, 173],
+    [244, 154, 123],
+    [222, 96, 77],
+    [180, 4, 38],
+];
+
+const GREYS: [[u8; 3]; 2] = [
+    [255, 255, 255],
+    [0, 0, 0],
+];
+
+impl Colormap {
+    /// Look up a colormap by its matplotlib name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "viridis" => Some(Colormap::Viridis),
+            "magma" => Some(Colormap::Magma),
+            "coolwarm" => Some(Colormap::Coolwarm),
+            "greys" | "grays" => Some(Colormap::Greys),
+            _ => None,
+        }
+    }
+
+    /// Color at `t`, which is clamped to between 0 and 1
+    pub fn color(&self, t: f32) -> Rgba<u8> {
+        let stops: &[[u8; 3]] = match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+            Colormap::Coolwarm => &COOLWARM,
+            Colormap::Greys => &GREYS,
+        };
+        let position = t.clamp(0., 1.) * (stops.len() - 1) as f32;
+        let index = (position.floor() as usize).min(stops.len() - 2);
+        let frac = position - index as f32;
+        let (a, b) = (stops[index], stops[index + 1]);
+        let mix = |k: usize| (a[k] as f32 + frac * (b[k] as f32 - a[k] as f32)).round() as u8;
+        Rgba([mix(0), mix(1), mix(2), 255])
+    }
+}
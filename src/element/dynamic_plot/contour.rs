@@ -0,0 +1,235 @@
+use std::collections::{HashMap, VecDeque};
+
+use image::Rgba;
+
+use crate::style::{Style, TextAlign, BLACK};
+use crate::CapStyle;
+
+use super::{format_value, Colormap, DynamicPlot, GridData, Interpolation, LineStyle};
+
+/// Space in pixels left between an inline level label and the line on either side of it
+const LABEL_GAP: f32 = 3.;
+
+/// How `PlotCommand::Contour` draws its levels
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContourStyle {
+    /// Colors the lines, or the bands between levels when filled, from the lowest level to the highest
+    pub colormap: Colormap,
+    /// One color for every line instead of the colormap. Filled contours only get lines if this is set.
+    pub color: Option<Rgba<u8>>,
+    pub ls: LineStyle,
+    /// Line width in pixels
+    pub line_width: f32,
+    /// Fill the bands between neighbouring levels. Values outside the lowest and highest levels are left empty.
+    pub filled: bool,
+    /// Write each level's value into a gap in its longest line
+    pub labels: bool,
+}
+
+impl Default for ContourStyle {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::Viridis,
+            color: None,
+            ls: LineStyle::Solid,
+            line_width: 1.5,
+            filled: false,
+            labels: false,
+        }
+    }
+}
+
+impl ContourStyle {
+    /// Color of a value between the lowest and highest levels
+    fn level_color(&self, levels: &[f32], value: f32) -> Rgba<u8> {
+        let (low, high) = (levels[0], levels[levels.len() - 1]);
+        let t = if high > low { (value - low) / (high - low) } else { 0.5 };
+        self.colormap.color(t)
+    }
+}
+
+/// An edge between two neighbouring grid values. `Horizontal(row, col)` joins (row, col) to (row, col + 1) and
+/// `Vertical(row, col)` joins (row, col) to (row + 1, col).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+}
+
+/// Lines where the grid crosses `level`, in data units, found by marching squares between the cell centers.
+/// Closed lines end on the point they start from.
+pub(super) fn contour_lines(grid: &GridData, level: f32) -> Vec<Vec<(f32, f32)>> {
+    let mut crossings = HashMap::new();
+    let mut segments = Vec::new();
+    for row in 0..grid.rows().saturating_sub(1) {
+        for col in 0..grid.cols().saturating_sub(1) {
+            let corners = [grid.value(row, col), grid.value(row, col + 1), grid.value(row + 1, col + 1), grid.value(row + 1, col)];
+            if corners.iter().any(|v| v.is_nan()) {continue;}
+            // The sides of the square, going round from the bottom, with the corners at either end
+            let sides = [
+                (Edge::Horizontal(row, col), (row, col), (row, col + 1)),
+                (Edge::Vertical(row, col + 1), (row, col + 1), (row + 1, col + 1)),
+                (Edge::Horizontal(row + 1, col), (row + 1, col), (row + 1, col + 1)),
+                (Edge::Vertical(row, col), (row, col), (row + 1, col)),
+            ];
+            let mut crossed = Vec::new();
+            for (edge, a, b) in sides {
+                let (va, vb) = (grid.value(a.0, a.1), grid.value(b.0, b.1));
+                if (va >= level) == (vb >= level) {continue;}
+                let t = (level - va) / (vb - va);
+                let (pa, pb) = (grid.node(a.0, a.1), grid.node(b.0, b.1));
+                crossings.insert(edge, (pa.0 + t * (pb.0 - pa.0), pa.1 + t * (pb.1 - pa.1)));
+                crossed.push(edge);
+            }
+            match crossed[..] {
+                [a, b] => segments.push((a, b)),
+                [bottom, right, top, left] => {
+                    // A saddle. The average of the corners decides whether the lower left corner joins the upper right.
+                    let center = corners.iter().sum::<f32>() / 4.;
+                    if (center >= level) == (corners[0] >= level) {
+                        segments.push((bottom, right));
+                        segments.push((top, left));
+                    } else {
+                        segments.push((left, bottom));
+                        segments.push((right, top));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    join_segments(&segments).into_iter()
+        .map(|chain| chain.iter().map(|edge| crossings[edge]).collect())
+        .collect()
+}
+
+/// Join segments which share an edge into chains. Each edge is crossed by at most two segments, so the chains never branch.
+fn join_segments(segments: &[(Edge, Edge)]) -> Vec<Vec<Edge>> {
+    let mut touching: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (k, (a, b)) in segments.iter().enumerate() {
+        touching.entry(*a).or_default().push(k);
+        touching.entry(*b).or_default().push(k);
+    }
+    let mut used = vec![false; segments.len()];
+    let mut chains = Vec::new();
+    for start in 0..segments.len() {
+        if used[start] {continue;}
+        used[start] = true;
+        let mut chain = VecDeque::from([segments[start].0, segments[start].1]);
+        for forward in [true, false] {
+            loop {
+                let end = if forward { chain[chain.len() - 1] } else { chain[0] };
+                let Some(&next) = touching[&end].iter().find(|k| !used[**k]) else { break };
+                used[next] = true;
+                let (a, b) = segments[next];
+                let other = if a == end { b } else { a };
+                if forward { chain.push_back(other) } else { chain.push_front(other) }
+            }
+        }
+        chains.push(chain.into_iter().collect());
+    }
+    chains
+}
+
+/// Length in pixels of a polyline
+fn path_length(points: &[(f32, f32)]) -> f32 {
+    points.windows(2).map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1)).sum()
+}
+
+/// Point a distance `s` in pixels along a polyline, and the index of the segment it is on
+fn point_along(points: &[(f32, f32)], s: f32) -> ((f32, f32), usize) {
+    let mut walked = 0.;
+    for (k, pair) in points.windows(2).enumerate() {
+        let length = (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1);
+        if walked + length >= s && length > 0. {
+            let t = (s - walked) / length;
+            return ((pair[0].0 + t * (pair[1].0 - pair[0].0), pair[0].1 + t * (pair[1].1 - pair[0].1)), k);
+        }
+        walked += length;
+    }
+    (points[points.len() - 1], points.len().saturating_sub(2))
+}
+
+/// A polyline with the stretch between `from` and `to` pixels along it left out, marked by a `None`
+fn cut_gap(points: &[(f32, f32)], from: f32, to: f32) -> Vec<Option<(f32, f32)>> {
+    let (start, start_segment) = point_along(points, from);
+    let (end, end_segment) = point_along(points, to);
+    let mut cut = points[..=start_segment].iter().map(|p| Some(*p)).collect::<Vec<_>>();
+    cut.push(Some(start));
+    cut.push(None);
+    cut.push(Some(end));
+    cut.extend(points[end_segment + 1..].iter().map(|p| Some(*p)));
+    cut
+}
+
+impl DynamicPlot {
+    /// Draw the lines of each level, and their labels if wanted
+    pub(super) fn draw_contour(&mut self, grid: &GridData, levels: &[f32], contour: &ContourStyle, style: &Style) {
+        let levels = sorted_levels(levels);
+        if levels.is_empty() {return;}
+        let draw_lines = !contour.filled || contour.color.is_some();
+        for level in &levels {
+            let color = contour.color.unwrap_or_else(|| contour.level_color(&levels, *level));
+            let mut lines = contour_lines(grid, *level).into_iter()
+                .map(|line| line.iter().map(|p| self.data_to_axis(*p).ok()).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+
+            // The label goes halfway along the longest line which is wholly in view and has room for it
+            let mut label = None;
+            if contour.labels {
+                let text = format_value(*level);
+                let half_width = style.text_size(&text).0 as f32 / 2. + LABEL_GAP;
+                let longest = lines.iter().enumerate()
+                    .filter(|(_, line)| line.iter().all(|p| p.is_some()))
+                    .map(|(k, line)| (k, line.iter().flatten().map(|p| self.axis_to_pixel(*p)).collect::<Vec<_>>()))
+                    .map(|(k, pixels)| (k, path_length(&pixels), pixels))
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((k, length, pixels)) = longest {
+                    if length > 4. * half_width {
+                        let (center, _) = point_along(&pixels, length / 2.);
+                        lines[k] = cut_gap(&pixels, length / 2. - half_width, length / 2. + half_width).into_iter()
+                            .map(|p| p.map(|p| self.pixel_to_axis(p)))
+                            .collect();
+                        label = Some((center, text));
+                    }
+                }
+            }
+
+            if draw_lines {
+                for line in &lines {
+                    self.draw_polyline(line, &contour.ls, contour.line_width, CapStyle::Round, color);
+                }
+            }
+            if let Some((center, text)) = label {
+                let text_color = if draw_lines { color } else { BLACK };
+                self.painter().draw_text(style, (center.0.round() as i32, center.1.round() as i32), &text, text_color, TextAlign::Center, TextAlign::Center);
+            }
+        }
+    }
+
+    /// Fill the bands between neighbouring levels, between the centers of the outer cells
+    pub(super) fn fill_contour(&mut self, grid: &GridData, levels: &[f32], contour: &ContourStyle) {
+        let levels = sorted_levels(levels);
+        if levels.len() < 2 || grid.rows() == 0 || grid.cols() == 0 || self.width == 0 || self.height == 0 {return;}
+        let (first, last) = (grid.node(0, 0), grid.node(grid.rows() - 1, grid.cols() - 1));
+        let x_range = (first.0.min(last.0), first.0.max(last.0));
+        let y_range = (first.1.min(last.1), first.1.max(last.1));
+        for i in self.axes_left()..=self.axes_right().min(self.width - 1) {
+            for j in self.axes_top()..=self.axes_bottom().min(self.height - 1) {
+                let (x, y) = self.axis_to_data(self.pixel_to_axis((i as f32, j as f32)));
+                if !(x >= x_range.0 && x <= x_range.1 && y >= y_range.0 && y <= y_range.1) {continue;}
+                let value = grid.sample(x, y, Interpolation::Bilinear);
+                let Some(band) = levels.windows(2).find(|pair| value >= pair[0] && value <= pair[1]) else { continue };
+                self.painter().set_pixel(i as i32, j as i32, contour.level_color(&levels, (band[0] + band[1]) / 2.));
+            }
+        }
+    }
+}
+
+/// The finite levels in increasing order, without repeats
+fn sorted_levels(levels: &[f32]) -> Vec<f32> {
+    let mut levels = levels.iter().copied().filter(|v| v.is_finite()).collect::<Vec<_>>();
+    levels.sort_by(f32::total_cmp);
+    levels.dedup();
+    levels
+}
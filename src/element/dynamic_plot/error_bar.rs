@@ -0,0 +1,103 @@
+use crate::CapStyle;
+
+use super::{DynamicPlot, LineStyle, ResolvedStyle};
+
+/// How far each point of `PlotCommand::ErrorBar` is uncertain along one axis
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Errors {
+    #[default]
+    None,
+    /// The same distance below and above each point
+    Symmetric(Vec<f32>),
+    /// Separate distances below and above each point
+    Asymmetric { lower: Vec<f32>, upper: Vec<f32> },
+}
+
+impl Errors {
+    /// Lower and upper ends of the error bar around the `k`-th value, or None if that value has no error
+    pub(super) fn ends(&self, k: usize, value: f32) -> Option<(f32, f32)> {
+        match self {
+            Errors::None => None,
+            Errors::Symmetric(errs) => errs.get(k).map(|e| (value - e.abs(), value + e.abs())),
+            Errors::Asymmetric { lower, upper } => Some((value - lower.get(k)?.abs(), value + upper.get(k)?.abs())),
+        }
+    }
+}
+
+/// Errors and drawing choices for `PlotCommand::ErrorBar`. The bars are as wide as the series' line width.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorBarOptions {
+    pub x_errs: Errors,
+    pub y_errs: Errors,
+    /// Length in pixels of the caps on either side of each bar end, or zero for no caps
+    pub cap_size: f32,
+    /// Draw the series' marker at each point
+    pub markers: bool,
+}
+
+impl Default for ErrorBarOptions {
+    fn default() -> Self {
+        Self {
+            x_errs: Errors::None,
+            y_errs: Errors::None,
+            cap_size: 3.,
+            markers: true,
+        }
+    }
+}
+
+impl ErrorBarOptions {
+    /// Every x and every y value the points and their bars reach, for fitting the axis limits
+    pub(super) fn extents(&self, xs: &[f32], ys: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let reach = |values: &[f32], errs: &Errors| values.iter().enumerate()
+            .flat_map(|(k, v)| match errs.ends(k, *v) {
+                Some((low, high)) => vec![*v, low, high],
+                None => vec![*v],
+            })
+            .collect::<Vec<_>>();
+        (reach(xs, &self.x_errs), reach(ys, &self.y_errs))
+    }
+}
+
+impl DynamicPlot {
+    pub(super) fn draw_error_bars(&mut self, xs: &[f32], ys: &[f32], options: &ErrorBarOptions, series: &ResolvedStyle) {
+        for (k, (x, y)) in xs.iter().zip(ys.iter()).enumerate() {
+            // Ends the axis scale cannot show, like negative values on a log axis, run uncapped to the edge of the axes
+            if let Some((low, high)) = options.x_errs.ends(k, *x) {
+                let shown = self.x_scale.contains(low);
+                let low = if shown { low } else { self.x_lim.0 };
+                let caps = (if shown { options.cap_size } else { 0. }, options.cap_size);
+                self.draw_error_bar((low, *y), (high, *y), false, caps, series);
+            }
+            if let Some((low, high)) = options.y_errs.ends(k, *y) {
+                let shown = self.y_scale.contains(low);
+                let low = if shown { low } else { self.y_lim.0 };
+                let caps = (if shown { options.cap_size } else { 0. }, options.cap_size);
+                self.draw_error_bar((*x, low), (*x, high), true, caps, series);
+            }
+        }
+        if options.markers {
+            for (x, y) in xs.iter().zip(ys.iter()) {
+                let Ok(point) = self.data_to_axis((*x, *y)) else { continue };
+                self.draw_marker(point, series);
+            }
+        }
+    }
+
+    /// Draw one bar between two points in data units. The ends which are in view get caps of the given sizes in pixels.
+    fn draw_error_bar(&mut self, a: (f32, f32), b: (f32, f32), vertical: bool, cap_sizes: (f32, f32), series: &ResolvedStyle) {
+        let points = self.clip_axis_aligned(&[a, b]);
+        self.draw_polyline(&points, &LineStyle::Solid, series.line_width, CapStyle::Butt, series.color);
+        for (end, cap_size) in [(a, cap_sizes.0), (b, cap_sizes.1)] {
+            if cap_size <= 0. {continue;}
+            let Ok(end) = self.data_to_axis(end) else { continue };
+            let (x, y) = self.axis_to_pixel(end);
+            let (from, to) = if vertical {
+                ((x - cap_size, y), (x + cap_size, y))
+            } else {
+                ((x, y - cap_size), (x, y + cap_size))
+            };
+            self.painter().draw_line(from, to, series.line_width, (CapStyle::Butt, CapStyle::Butt), series.color);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use image::Rgba;
+
+use super::DynamicPlot;
+
+impl DynamicPlot {
+    /// Fill polygons given in pixels as one shape, clipped to the inside of the axes
+    pub(super) fn fill_polygons(&mut self, polygons: &[Vec<(f32, f32)>], color: Rgba<u8>) {
+        let clip = self.axes_clip();
+        let mut painter = self.painter();
+        painter.set_clip(clip);
+        painter.fill_polygons(polygons, color);
+    }
+
+    /// Polygons filling between two curves, one for each run of points which are not masked out and can be shown.
+    /// The curves are given by `lows` and `highs` at the positions `along`, which run along x, or along y if `horizontal`.
+    pub(super) fn band_polygons(&self, along: &[f32], lows: &[f32], highs: &[f32], mask: Option<&[bool]>, horizontal: bool) -> Vec<Vec<(f32, f32)>> {
+        let pixel = |a: f32, v: f32| {
+            let point = if horizontal { (v, a) } else { (a, v) };
+            self.axis_to_pixel(self.unprotected_data_to_axis(point))
+        };
+        let finite = |p: (f32, f32)| p.0.is_finite() && p.1.is_finite();
+        let mut polygons = Vec::new();
+        let mut run: Vec<((f32, f32), (f32, f32))> = Vec::new();
+        let n = along.len().min(lows.len()).min(highs.len());
+        for k in 0..=n {
+            let pair = (k < n).then(|| (pixel(along[k], lows[k]), pixel(along[k], highs[k])));
+            let shown = mask.is_none_or(|mask| mask.get(k).copied().unwrap_or(false));
+            match pair {
+                Some((low, high)) if shown && finite(low) && finite(high) => run.push((low, high)),
+                _ => {
+                    // Out along one curve and back along the other
+                    if run.len() >= 2 {
+                        polygons.push(run.iter().map(|p| p.0).chain(run.iter().rev().map(|p| p.1)).collect());
+                    }
+                    run.clear();
+                },
+            }
+        }
+        polygons
+    }
+}
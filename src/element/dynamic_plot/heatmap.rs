@@ -0,0 +1,225 @@
+use crate::style::{Style, TextAlign, BLACK};
+
+use super::ticks::{label_stride, MINOR_TICK_LENGTH, TICK_LENGTH};
+use super::{AxisScale, Colormap, DynamicPlot, PlotCommand};
+
+/// Space in pixels between the axes and the colorbar
+pub(super) const COLORBAR_GAP: u32 = 10;
+/// Width in pixels of the colorbar
+pub(super) const COLORBAR_WIDTH: u32 = 12;
+
+/// Values on a regular grid of cells, stored row by row. Row 0 is at the bottom of the extent and column 0 at the left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridData {
+    values: Vec<f32>,
+    rows: usize,
+    cols: usize,
+    extent: (f32, f32, f32, f32),
+}
+
+impl GridData {
+    /// Panics if there are not `rows * cols` values
+    pub fn new(values: Vec<f32>, rows: usize, cols: usize, extent: (f32, f32, f32, f32)) -> Self {
+        assert_eq!(values.len(), rows * cols, "a {} by {} grid needs {} values", rows, cols, rows * cols);
+        Self { values, rows, cols, extent }
+    }
+
+    /// The values, row by row from the bottom
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Outer edges of the cells in data units, as (x_min, x_max, y_min, y_max)
+    pub fn extent(&self) -> (f32, f32, f32, f32) {
+        self.extent
+    }
+
+    pub(super) fn value(&self, row: usize, col: usize) -> f32 {
+        self.values[row * self.cols + col]
+    }
+
+    /// Position in data units of the center of a cell, where its value sits
+    pub(super) fn node(&self, row: usize, col: usize) -> (f32, f32) {
+        (
+            self.extent.0 + (col as f32 + 0.5) / self.cols as f32 * (self.extent.1 - self.extent.0),
+            self.extent.2 + (row as f32 + 0.5) / self.rows as f32 * (self.extent.3 - self.extent.2),
+        )
+    }
+
+    /// Value of the grid at a point in data units, or NaN outside the extent
+    pub(super) fn sample(&self, x: f32, y: f32, interpolation: Interpolation) -> f32 {
+        if self.rows == 0 || self.cols == 0 {
+            return f32::NAN;
+        }
+        // Position in cells from the lower left corner
+        let u = (x - self.extent.0) / (self.extent.1 - self.extent.0) * self.cols as f32;
+        let v = (y - self.extent.2) / (self.extent.3 - self.extent.2) * self.rows as f32;
+        if !(u >= 0. && u <= self.cols as f32 && v >= 0. && v <= self.rows as f32) {
+            return f32::NAN;
+        }
+        match interpolation {
+            Interpolation::Nearest => self.value((v as usize).min(self.rows - 1), (u as usize).min(self.cols - 1)),
+            Interpolation::Bilinear => {
+                // Values sit at the cell centers, and the outer half cells take the value of the nearest center
+                let u = (u - 0.5).clamp(0., (self.cols - 1) as f32);
+                let v = (v - 0.5).clamp(0., (self.rows - 1) as f32);
+                let (col, row) = (u.floor() as usize, v.floor() as usize);
+                let (next_col, next_row) = ((col + 1).min(self.cols - 1), (row + 1).min(self.rows - 1));
+                let (fu, fv) = (u - col as f32, v - row as f32);
+                let bottom = self.value(row, col) * (1. - fu) + self.value(row, next_col) * fu;
+                let top = self.value(next_row, col) * (1. - fu) + self.value(next_row, next_col) * fu;
+                bottom * (1. - fv) + top * fv
+            },
+        }
+    }
+}
+
+/// How an image is filled in between the centers of its cells
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Interpolation {
+    /// Each cell is one flat color
+    #[default]
+    Nearest,
+    /// Colors blend smoothly between neighbouring cells
+    Bilinear,
+}
+
+/// How `PlotCommand::ImShow` turns values into colors
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageStyle {
+    pub colormap: Colormap,
+    /// Value at the bottom of the colormap. Defaults to the smallest value the normalization can show.
+    pub vmin: Option<f32>,
+    /// Value at the top of the colormap. Defaults to the largest value the normalization can show.
+    pub vmax: Option<f32>,
+    /// How values are spread over the colormap. `AxisScale::Log` gives each decade the same share.
+    pub norm: AxisScale,
+    pub interpolation: Interpolation,
+    /// Draw a colorbar to the right of the axes. Only the last image with a colorbar gets one.
+    pub colorbar: bool,
+    /// Label of the colorbar, or empty for none
+    pub label: String,
+}
+
+impl Default for ImageStyle {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::Viridis,
+            vmin: None,
+            vmax: None,
+            norm: AxisScale::Linear,
+            interpolation: Interpolation::Nearest,
+            colorbar: true,
+            label: String::new(),
+        }
+    }
+}
+
+impl ImageStyle {
+    /// Values at the bottom and top of the colormap. Limits the normalization cannot show are replaced by its defaults.
+    pub(super) fn color_lim(&self, grid: &GridData) -> (f32, f32) {
+        let range = self.norm.data_range(&grid.values);
+        let lim = match (self.vmin.or(range.map(|r| r.0)), self.vmax.or(range.map(|r| r.1))) {
+            (Some(low), Some(high)) => (low, high),
+            _ => return self.norm.default_lim(),
+        };
+        if !(self.norm.contains(lim.0) && self.norm.contains(lim.1)) {
+            self.norm.default_lim()
+        } else if lim.0 == lim.1 {
+            self.norm.widen(lim, 0.)
+        } else {
+            lim
+        }
+    }
+
+    /// Label of the colorbar, followed by any offset or multiplier shared by its tick labels
+    pub(super) fn colorbar_label(&self, annotation: Option<&str>) -> String {
+        match annotation {
+            Some(annotation) if self.label.is_empty() => annotation.to_owned(),
+            Some(annotation) => format!("{} ({})", self.label, annotation),
+            None => self.label.clone(),
+        }
+    }
+}
+
+impl DynamicPlot {
+    /// The image which gets the colorbar, if any
+    pub(super) fn colorbar_image<'a>(&self, commands: &'a [PlotCommand]) -> Option<(&'a GridData, &'a ImageStyle)> {
+        commands.iter().rev().find_map(|command| match command {
+            PlotCommand::ImShow { grid, style } if style.colorbar => Some((grid, style)),
+            _ => None,
+        })
+    }
+
+    /// Draw every image and filled contour into the axes. They go down before the grid and axes so that those show on top.
+    pub(super) fn draw_images(&mut self, commands: &[PlotCommand]) {
+        for command in commands {
+            match command {
+                PlotCommand::ImShow { grid, style } => self.draw_image(grid, style),
+                PlotCommand::Contour { grid, levels, style } if style.filled => self.fill_contour(grid, levels, style),
+                _ => (),
+            }
+        }
+    }
+
+    fn draw_image(&mut self, grid: &GridData, image: &ImageStyle) {
+        if self.width == 0 || self.height == 0 {return;}
+        let lim = image.color_lim(grid);
+        for i in self.axes_left()..=self.axes_right().min(self.width - 1) {
+            for j in self.axes_top()..=self.axes_bottom().min(self.height - 1) {
+                let (x, y) = self.axis_to_data(self.pixel_to_axis((i as f32, j as f32)));
+                let value = grid.sample(x, y, image.interpolation);
+                // Cells outside the grid, missing values and values the normalization cannot show are left empty
+                let t = image.norm.value_to_axis(value, lim);
+                if !t.is_finite() {continue;}
+                self.painter().set_pixel(i as i32, j as i32, image.colormap.color(t));
+            }
+        }
+    }
+
+    /// Draw the colorbar of the last image with one, with its ticks and label, beside the axes and any secondary axis
+    pub(super) fn draw_colorbar(&mut self, commands: &[PlotCommand], style: &Style) {
+        let Some((grid, image)) = self.colorbar_image(commands) else { return };
+        let lim = image.color_lim(grid);
+        let (top, bottom) = (self.axes_top(), self.axes_bottom());
+        let left = self.axes_right() + self.margins.secondary + COLORBAR_GAP;
+        let right = left + COLORBAR_WIDTH;
+        let mut painter = self.painter();
+        for j in top..=bottom {
+            let color = image.colormap.color((bottom - j) as f32 / (bottom - top).max(1) as f32);
+            painter.draw_h_line(j as i32, (left as i32, right as i32 - 1), color);
+        }
+        painter.draw_rect((left as f32, top as f32, right as f32, bottom as f32), 1., BLACK);
+
+        // The colorbar runs up the full height of the axes, so its ticks sit as a y axis' would
+        let ticks = self.vertical_ticks(image.norm, lim);
+        for (y, _) in &ticks.majors {
+            self.draw_tick(*y, right, TICK_LENGTH, -1, false);
+        }
+        for y in &ticks.minors {
+            self.draw_tick(*y, right, MINOR_TICK_LENGTH, -1, false);
+        }
+        let label_left = right + self.tick_label_offset();
+        let stride = label_stride(&self.y_label_boxes(&ticks, label_left, true, style));
+        for (y, label) in ticks.majors.iter().step_by(stride) {
+            self.painter().draw_text(style, (label_left as i32, *y as i32), label, BLACK, TextAlign::Center, TextAlign::UpperLeft);
+        }
+
+        let label = image.colorbar_label(ticks.annotation.as_deref());
+        if !label.is_empty() {
+            // Reads downwards, at the outer edge of the colorbar's margin
+            let text_height = style.text_size("").1;
+            let x = self.axes_right() + self.margins.secondary + self.margins.colorbar - text_height / 2;
+            let center_y = (top + bottom) / 2;
+            self.painter().draw_rotated_text(style, (x as i32, center_y as i32), &label, BLACK, TextAlign::Center, TextAlign::Center, 270.);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use super::{DynamicPlot, LineStyle, ResolvedStyle};
+
+/// Most bins a rule may choose, so that a few outliers cannot make the histogram enormous
+const MAX_AUTO_BINS: usize = 1000;
+
+/// How a histogram's samples are split into bins
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bins {
+    /// This many bins of equal width across the samples. Zero is taken as one. The count is used as given, however
+    /// large, so a very large count costs a matching amount of memory and drawing.
+    Count(usize),
+    /// Bin edges in increasing order. Samples outside the first and last edges are left out.
+    Edges(Vec<f32>),
+    /// Width twice the interquartile range over the cube root of the sample count, which resists outliers
+    FreedmanDiaconis,
+    /// log₂ of the sample count plus one bins, suited to small, roughly normal samples
+    Sturges,
+    /// Width 3.49 standard deviations over the cube root of the sample count, suited to roughly normal samples
+    Scott,
+}
+
+impl Default for Bins {
+    fn default() -> Self {
+        Bins::Count(10)
+    }
+}
+
+/// How a histogram is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HistType {
+    /// A filled bar for each bin
+    #[default]
+    Bar,
+    /// The unfilled outline of the bars
+    Step,
+    /// The outline of the bars, filled
+    StepFilled,
+}
+
+/// Binning and drawing choices for `PlotCommand::Hist`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistOptions {
+    /// How much each sample counts for, in the same order as the samples. Samples without a weight count once.
+    pub weights: Option<Vec<f32>>,
+    /// Divide the counts by the total weight and the bin width, so that the bars have a total area of one
+    pub density: bool,
+    /// Each bin counts every sample up to its right edge
+    pub cumulative: bool,
+    pub histtype: HistType,
+}
+
+/// Linearly interpolated quantile of sorted values, for `q` between 0 and 1
+fn quantile(sorted: &[f32], q: f32) -> f32 {
+    let position = q * (sorted.len() - 1) as f32;
+    let below = position.floor() as usize;
+    let above = (below + 1).min(sorted.len() - 1);
+    sorted[below] + (position - below as f32) * (sorted[above] - sorted[below])
+}
+
+/// Edges of the bins for finite, sorted samples
+fn bin_edges(sorted: &[f32], bins: &Bins) -> Vec<f32> {
+    if let Bins::Edges(edges) = bins {
+        return edges.clone();
+    }
+    let (mut low, mut high) = match (sorted.first(), sorted.last()) {
+        (Some(low), Some(high)) => (*low, *high),
+        _ => (0., 1.),
+    };
+    // Samples which are all the same get a bin of width one around them
+    if low == high {
+        low -= 0.5;
+        high += 0.5;
+    }
+    let n = sorted.len().max(1) as f32;
+    let from_width = |width: f32| if width > 0. { ((high - low) / width).ceil() as usize } else { 1 };
+    let count = match bins {
+        Bins::Count(count) => (*count).max(1),
+        Bins::Sturges => (n.log2().ceil() as usize + 1).min(MAX_AUTO_BINS),
+        Bins::Scott => {
+            let mean = sorted.iter().sum::<f32>() / n;
+            let std = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt();
+            from_width(3.49 * std / n.cbrt()).clamp(1, MAX_AUTO_BINS)
+        },
+        Bins::FreedmanDiaconis if sorted.is_empty() => 1,
+        Bins::FreedmanDiaconis => {
+            let iqr = quantile(sorted, 0.75) - quantile(sorted, 0.25);
+            from_width(2. * iqr / n.cbrt()).clamp(1, MAX_AUTO_BINS)
+        },
+        Bins::Edges(_) => unreachable!(),
+    };
+    (0..=count).map(|k| low + (high - low) * k as f32 / count as f32).collect()
+}
+
+/// Bin edges and the height of each bin. Non-finite samples are left out, and the last bin includes its right edge.
+pub fn histogram(samples: &[f32], bins: &Bins, options: &HistOptions) -> (Vec<f32>, Vec<f32>) {
+    let weight = |k: usize| options.weights.as_ref().and_then(|w| w.get(k).copied()).unwrap_or(1.);
+    let mut weighted = samples.iter().enumerate()
+        .filter(|(_, v)| v.is_finite())
+        .map(|(k, v)| (*v, weight(k)))
+        .collect::<Vec<_>>();
+    weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let sorted = weighted.iter().map(|(v, _)| *v).collect::<Vec<_>>();
+    let edges = bin_edges(&sorted, bins);
+    if edges.len() < 2 {
+        return (edges, Vec::new());
+    }
+
+    let mut heights = vec![0.; edges.len() - 1];
+    let last = edges[edges.len() - 1];
+    for (value, w) in &weighted {
+        if *value < edges[0] || *value > last {continue;}
+        // The first edge above the value closes its bin
+        let bin = edges.partition_point(|edge| edge <= value).clamp(1, heights.len()) - 1;
+        heights[bin] += w;
+    }
+
+    let total = heights.iter().sum::<f32>();
+    if options.cumulative {
+        let mut sum = 0.;
+        for height in heights.iter_mut() {
+            sum += *height;
+            *height = if options.density { sum / total } else { sum };
+        }
+    } else if options.density {
+        for (height, pair) in heights.iter_mut().zip(edges.windows(2)) {
+            *height /= total * (pair[1] - pair[0]);
+        }
+    }
+    (edges, heights)
+}
+
+impl DynamicPlot {
+    pub(super) fn draw_hist(&mut self, edges: &[f32], heights: &[f32], histtype: HistType, series: &ResolvedStyle) {
+        if heights.is_empty() {return;}
+        // Bars rise from zero, or from the bottom of the axes when zero cannot be shown
+        let base = if self.y_scale.contains(0.) { 0. } else { self.y_lim.0 };
+        let heights = heights.iter().map(|h| if self.y_scale.contains(*h) { *h } else { base }).collect::<Vec<_>>();
+
+        let mut outline = vec![(edges[0], base)];
+        for (k, height) in heights.iter().enumerate() {
+            outline.push((edges[k], *height));
+            outline.push((edges[k + 1], *height));
+        }
+        outline.push((edges[edges.len() - 1], base));
+        if histtype != HistType::Step {
+            // One shape for all the bars, so that neighbouring bars meet without a seam
+            let shape = outline.iter().map(|p| self.axis_to_pixel(self.unprotected_data_to_axis(*p))).collect();
+            self.fill_polygons(&[shape], series.color);
+        }
+        if histtype != HistType::Bar {
+            let points = self.clip_axis_aligned(&outline);
+            self.draw_polyline(&points, &LineStyle::Solid, series.line_width, series.cap, series.color);
+        }
+    }
+
+    /// Axis coordinates of a path of horizontal and vertical segments given in data units, cut off at the edges of the
+    /// axes. Segments wholly outside are replaced by gaps.
+    pub(super) fn clip_axis_aligned(&self, points: &[(f32, f32)]) -> Vec<Option<(f32, f32)>> {
+        let axis = points.iter().map(|p| self.unprotected_data_to_axis(*p)).collect::<Vec<_>>();
+        let outside = |a: f32, b: f32| (a < 0. && b < 0.) || (a > 1. && b > 1.);
+        let mut clipped = Vec::new();
+        for (k, p) in axis.iter().enumerate() {
+            clipped.push(Some((p.0.clamp(0., 1.), p.1.clamp(0., 1.))));
+            if let Some(next) = axis.get(k + 1) {
+                if outside(p.0, next.0) || outside(p.1, next.1) {
+                    clipped.push(None);
+                }
+            }
+        }
+        clipped
+    }
+}
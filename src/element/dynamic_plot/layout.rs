@@ -0,0 +1,227 @@
+use crate::style::{Style, TextAlign, BLACK};
+
+use super::heatmap::{COLORBAR_GAP, COLORBAR_WIDTH};
+use super::legend::LEGEND_MARGIN;
+use super::ticks::{AxisTicks, TICK_LENGTH};
+use super::{AxesStyle, DynamicPlot, LegendLoc, PlotCommand, TickDirection};
+
+/// Space in pixels left between text and the edges of the plot
+const EDGE_PAD: u32 = 4;
+/// Space in pixels between an axis and its tick labels
+const TICK_LABEL_PAD: u32 = 3;
+/// Space in pixels between the tick labels and the axis label, and between the title and the axes
+const LABEL_PAD: u32 = 4;
+/// Passes in which the margins may shrink as well as grow
+const FREE_LAYOUT_PASSES: usize = 2;
+/// Most passes taken to settle the margins
+const MAX_LAYOUT_PASSES: usize = 8;
+
+/// Space in pixels between the edges of the plot and the edges of the axes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct Margins {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    /// Width of the secondary axis' tick labels and label, which is part of `right`
+    pub secondary: u32,
+    /// Width of the colorbar with its tick labels and label, which is part of `right` and comes after `secondary`
+    pub colorbar: u32,
+}
+
+/// A second y axis on the right, showing the primary y values converted to other units
+#[derive(Clone, Copy)]
+pub(super) struct SecondaryAxis<'a> {
+    pub label: &'a str,
+    pub forward: fn(f32) -> f32,
+    pub inverse: fn(f32) -> f32,
+}
+
+/// Text drawn around the axes, from the last command setting each. Labels of hidden axes are left out.
+struct AxisLabels<'a> {
+    title: Option<&'a str>,
+    x: Option<&'a str>,
+    y: Option<&'a str>,
+}
+
+impl<'a> AxisLabels<'a> {
+    fn find(commands: &'a [PlotCommand], axes_style: &AxesStyle) -> Self {
+        let mut labels = Self { title: None, x: None, y: None };
+        for command in commands {
+            match command {
+                PlotCommand::SetTitle { title } => labels.title = Some(title),
+                PlotCommand::SetXLabel { label } => labels.x = Some(label),
+                PlotCommand::SetYLabel { label } => labels.y = Some(label),
+                _ => (),
+            }
+        }
+        if !axes_style.show_x {
+            labels.x = None;
+        }
+        if !axes_style.show_y {
+            labels.y = None;
+        }
+        labels
+    }
+}
+
+impl DynamicPlot {
+    pub(super) fn axes_left(&self) -> u32 {
+        self.margins.left
+    }
+
+    pub(super) fn axes_right(&self) -> u32 {
+        self.width - self.margins.right
+    }
+
+    pub(super) fn axes_top(&self) -> u32 {
+        self.margins.top
+    }
+
+    pub(super) fn axes_bottom(&self) -> u32 {
+        self.height - self.margins.bottom
+    }
+
+    pub(super) fn axes_width(&self) -> u32 {
+        self.axes_right() - self.axes_left()
+    }
+
+    pub(super) fn axes_height(&self) -> u32 {
+        self.axes_bottom() - self.axes_top()
+    }
+
+    /// The pixels inside the axes, leaving out the spines, as a clip rectangle for `Painter::set_clip`
+    pub(super) fn axes_clip(&self) -> (u32, u32, u32, u32) {
+        (self.axes_left() + 1, self.axes_top() + 1, self.axes_right(), self.axes_bottom())
+    }
+
+    /// Distance in pixels from an axis to its tick labels, clearing the ticks if they point outwards
+    pub(super) fn tick_label_offset(&self) -> u32 {
+        match self.axes_style.tick_direction {
+            TickDirection::In => TICK_LABEL_PAD,
+            TickDirection::Out => TICK_LABEL_PAD + TICK_LENGTH,
+        }
+    }
+
+    pub(super) fn secondary_axis<'a>(&self, commands: &'a [PlotCommand]) -> Option<SecondaryAxis<'a>> {
+        commands.iter().rev().find_map(|command| match command {
+            PlotCommand::SecondaryYAxis { label, forward, inverse } => Some(SecondaryAxis { label, forward: *forward, inverse: *inverse }),
+            _ => None,
+        })
+    }
+
+    /// Size the margins to fit the tick labels, axis labels, title, secondary axis, colorbar and any legend outside the axes.
+    /// Which tick labels there are depends on the size of the axes, so the margins are sized again until they stop changing.
+    /// Some sizes flip between two sets of ticks, each fitting in the margins the other needs, so after the first passes
+    /// the margins only grow. They then settle on ticks whose labels fit.
+    pub(super) fn compute_layout(&mut self, commands: &[PlotCommand], style: &Style) {
+        let labels = AxisLabels::find(commands, &self.axes_style);
+        let secondary = self.secondary_axis(commands);
+        let outside_legend = commands.iter().rev().find_map(|command| match command {
+            PlotCommand::Legend { labels, loc } => Some((labels, *loc)),
+            _ => None,
+        }).filter(|(_, loc)| *loc == LegendLoc::OutsideRight)
+            .and_then(|(labels, _)| self.legend_size(commands, labels, style));
+        let colorbar = self.colorbar_image(commands);
+        let text_height = style.text_size("").1;
+        let label_offset = self.tick_label_offset();
+        let no_ticks = || AxisTicks { majors: Vec::new(), minors: Vec::new(), annotation: None };
+
+        self.margins = Margins {
+            left: 2 * style.font_size,
+            right: EDGE_PAD,
+            top: EDGE_PAD,
+            bottom: 2 * style.font_size,
+            secondary: 0,
+            colorbar: 0,
+        };
+        for pass in 0..MAX_LAYOUT_PASSES {
+            let x_ticks = if self.axes_style.show_x { self.x_ticks() } else { no_ticks() };
+            let y_ticks = if self.axes_style.show_y { self.y_ticks() } else { no_ticks() };
+            let (axes_left, axes_right) = (self.axes_left() as i32, self.axes_right() as i32);
+            let (axes_top, axes_bottom) = (self.axes_top() as i32, self.axes_bottom() as i32);
+
+            // How far the tick labels at the ends of each axis stick out past the axes
+            let mut overhang = Margins::default();
+            for (x, label) in &x_ticks.majors {
+                let half_width = style.text_size(label).0 as i32 / 2;
+                overhang.left = overhang.left.max((half_width - (*x as i32 - axes_left)).max(0) as u32);
+                overhang.right = overhang.right.max((half_width - (axes_right - *x as i32)).max(0) as u32);
+            }
+            let y_label_width = y_ticks.majors.iter().map(|(_, label)| style.text_size(label).0).max().unwrap_or(0);
+            for (y, _) in &y_ticks.majors {
+                let half_height = text_height as i32 / 2;
+                overhang.top = overhang.top.max((half_height - (*y as i32 - axes_top)).max(0) as u32);
+                overhang.bottom = overhang.bottom.max((half_height - (axes_bottom - *y as i32)).max(0) as u32);
+            }
+
+            let axis_label = |label: Option<&str>| if label.is_some() { text_height + LABEL_PAD } else { 0 };
+            let x_tick_height = if x_ticks.majors.is_empty() { 0 } else { label_offset + text_height };
+            let secondary_width = match secondary {
+                Some(axis) => {
+                    let ticks = self.secondary_ticks(&axis);
+                    let width = ticks.majors.iter().map(|(_, label)| style.text_size(label).0).max().unwrap_or(0);
+                    label_offset + width + axis_label((!axis.label.is_empty()).then_some(axis.label))
+                },
+                None => 0,
+            };
+            let colorbar_width = match colorbar {
+                Some((grid, image)) => {
+                    let ticks = self.vertical_ticks(image.norm, image.color_lim(grid));
+                    let width = ticks.majors.iter().map(|(_, label)| style.text_size(label).0).max().unwrap_or(0);
+                    let label = image.colorbar_label(ticks.annotation.as_deref());
+                    COLORBAR_GAP + COLORBAR_WIDTH + label_offset + width + axis_label((!label.is_empty()).then_some(&label))
+                },
+                None => 0,
+            };
+            let legend_width = outside_legend.map(|(width, _)| LEGEND_MARGIN + width).unwrap_or(0);
+
+            let y_tick_width = if y_ticks.majors.is_empty() { 0 } else { y_label_width + label_offset };
+            let left = (EDGE_PAD + axis_label(labels.y) + y_tick_width).max(EDGE_PAD + overhang.left);
+            let right = (EDGE_PAD + secondary_width + colorbar_width + legend_width).max(EDGE_PAD + overhang.right);
+            let top = (EDGE_PAD + axis_label(labels.title)).max(EDGE_PAD + overhang.top);
+            let bottom = (EDGE_PAD + x_tick_height + axis_label(labels.x)).max(EDGE_PAD + overhang.bottom);
+            let (left, right, top, bottom) = if pass < FREE_LAYOUT_PASSES {
+                (left, right, top, bottom)
+            } else {
+                (left.max(self.margins.left), right.max(self.margins.right), top.max(self.margins.top), bottom.max(self.margins.bottom))
+            };
+
+            // Always leave at least a pixel of axes
+            let left = left.min(self.width.saturating_sub(1));
+            let right = right.min(self.width.saturating_sub(left + 1));
+            let top = top.min(self.height.saturating_sub(1));
+            let bottom = bottom.min(self.height.saturating_sub(top + 1));
+            let margins = Margins { left, right, top, bottom, secondary: secondary_width, colorbar: colorbar_width };
+            if margins == self.margins {
+                break;
+            }
+            self.margins = margins;
+        }
+    }
+
+    /// Draw the title and the axis labels in the margins
+    pub(super) fn draw_labels(&mut self, commands: &[PlotCommand], style: &Style) {
+        let labels = AxisLabels::find(commands, &self.axes_style);
+        let text_height = style.text_size("").1;
+        let center_x = (self.axes_left() + self.axes_right()) / 2;
+        let center_y = (self.axes_top() + self.axes_bottom()) / 2;
+        if let Some(title) = labels.title {
+            self.painter().draw_text(style, (center_x as i32, EDGE_PAD as i32), title, BLACK, TextAlign::UpperLeft, TextAlign::Center);
+        }
+        if let Some(label) = labels.x {
+            let top = self.axes_bottom() + self.tick_label_offset() + text_height + LABEL_PAD;
+            self.painter().draw_text(style, (center_x as i32, top as i32), label, BLACK, TextAlign::UpperLeft, TextAlign::Center);
+        }
+        if let Some(label) = labels.y {
+            self.painter().draw_rotated_text(style, ((EDGE_PAD + text_height / 2) as i32, center_y as i32), label, BLACK, TextAlign::Center, TextAlign::Center, 90.);
+        }
+        if let Some(axis) = self.secondary_axis(commands) {
+            if !axis.label.is_empty() {
+                // Reads downwards, facing the axes
+                let x = self.axes_right() + self.margins.secondary - text_height / 2;
+                self.painter().draw_rotated_text(style, (x as i32, center_y as i32), axis.label, BLACK, TextAlign::Center, TextAlign::Center, 270.);
+            }
+        }
+    }
+}
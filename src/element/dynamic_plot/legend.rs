@@ -0,0 +1,175 @@
+use crate::{CapStyle, Color};
+use crate::style::{Style, TextAlign, BLACK, WHITE};
+
+use super::{DynamicPlot, HistType, PlotCommand, ResolvedStyle};
+
+/// Distance in pixels between the legend and the edge of the axes
+pub(super) const LEGEND_MARGIN: u32 = 8;
+/// Space in pixels between the legend border and its contents
+const LEGEND_PADDING: u32 = 6;
+/// Width in pixels of the sample drawn for each series
+const SAMPLE_WIDTH: u32 = 28;
+const SAMPLE_GAP: u32 = 6;
+const BACKGROUND_ALPHA: f32 = 0.85;
+
+/// Where to put the legend in the axes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LegendLoc {
+    /// The corner which covers the least of the plot
+    Best,
+    UpperRight,
+    UpperLeft,
+    LowerLeft,
+    LowerRight,
+    /// To the right of the axes, which shrink to make room
+    OutsideRight,
+}
+
+/// A labelled entry in the legend
+struct Entry<'a> {
+    command: &'a PlotCommand,
+    series: &'a ResolvedStyle,
+    label: &'a str,
+}
+
+impl DynamicPlot {
+    /// Draw a legend for the series in `commands`. The n-th label belongs to the n-th series, and series without a label are skipped.
+    pub(super) fn draw_legend(&mut self, commands: &[PlotCommand], series_styles: &[ResolvedStyle], labels: &[Option<String>], loc: LegendLoc, style: &Style) {
+        let entries = commands.iter()
+            .zip(series_styles.iter())
+            .filter(|(c, _)| c.is_series())
+            .zip(labels.iter())
+            .filter_map(|((command, series), label)| label.as_ref().map(|label| Entry { command, series, label }))
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            return;
+        }
+
+        let row_height = style.text_size("").1;
+        let Some((width, height)) = self.legend_size(commands, labels, style) else { return };
+        let (left, top) = self.legend_position(loc, width, height);
+
+        // Box, with a one pixel border around the centers of the outermost pixels
+        let (box_left, box_top) = (left as f32, top as f32);
+        let (box_right, box_bottom) = (box_left + width as f32 - 1., box_top + height as f32 - 1.);
+        let background = Color::from(WHITE).faded(BACKGROUND_ALPHA).into();
+        let mut painter = self.painter();
+        painter.fill_rect((box_left + 0.5, box_top + 0.5, box_right - 0.5, box_bottom - 0.5), background);
+        painter.draw_rect((box_left, box_top, box_right, box_bottom), 1., BLACK);
+
+        for (row, entry) in entries.iter().enumerate() {
+            let row_top = top + LEGEND_PADDING + row as u32 * row_height;
+            let center_y = row_top as f32 + row_height as f32 / 2.;
+            let sample_left = (left + LEGEND_PADDING) as f32;
+            self.draw_legend_sample(entry.command, entry.series, (sample_left, center_y), row_height as f32);
+            self.painter().draw_text(
+                style, ((left + LEGEND_PADDING + SAMPLE_WIDTH + SAMPLE_GAP) as i32, center_y.round() as i32),
+                entry.label, BLACK, TextAlign::Center, TextAlign::UpperLeft
+            );
+        }
+    }
+
+    /// Width and height in pixels of the legend box, or None if no series is labelled
+    pub(super) fn legend_size(&self, commands: &[PlotCommand], labels: &[Option<String>], style: &Style) -> Option<(u32, u32)> {
+        let labels = commands.iter()
+            .filter(|c| c.is_series())
+            .zip(labels.iter())
+            .filter_map(|(_, label)| label.as_deref())
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            return None;
+        }
+        let row_height = style.text_size("").1;
+        let text_width = labels.iter().map(|label| style.text_size(label).0).max().unwrap_or(0);
+        Some((
+            2 * LEGEND_PADDING + SAMPLE_WIDTH + SAMPLE_GAP + text_width,
+            2 * LEGEND_PADDING + row_height * labels.len() as u32,
+        ))
+    }
+
+    /// Upper left corner of the legend box, in pixels
+    fn legend_position(&self, loc: LegendLoc, width: u32, height: u32) -> (u32, u32) {
+        let corner = |loc: LegendLoc| {
+            let left = self.axes_left() + LEGEND_MARGIN;
+            let right = self.axes_right().saturating_sub(LEGEND_MARGIN + width).max(left);
+            let top = self.axes_top() + LEGEND_MARGIN;
+            let bottom = self.axes_bottom().saturating_sub(LEGEND_MARGIN + height).max(top);
+            match loc {
+                LegendLoc::UpperLeft => (left, top),
+                LegendLoc::LowerLeft => (left, bottom),
+                LegendLoc::LowerRight => (right, bottom),
+                LegendLoc::UpperRight | LegendLoc::Best => (right, top),
+                // Past the secondary axis and colorbar, if there are any
+                LegendLoc::OutsideRight => (self.axes_right() + self.margins.secondary + self.margins.colorbar + LEGEND_MARGIN, self.axes_top()),
+            }
+        };
+        if loc != LegendLoc::Best {
+            return corner(loc);
+        }
+
+        // Count the pixels which have already been drawn on in each corner. Ties go to the earlier corner.
+        let candidates = [LegendLoc::UpperRight, LegendLoc::UpperLeft, LegendLoc::LowerLeft, LegendLoc::LowerRight];
+        let mut best = (usize::MAX, corner(LegendLoc::UpperRight));
+        for candidate in candidates {
+            let (left, top) = corner(candidate);
+            let mut occupied = 0;
+            for i in left..(left + width).min(self.width) {
+                for j in top..(top + height).min(self.height) {
+                    if self.pixels[(i, j)] != WHITE {
+                        occupied += 1;
+                    }
+                }
+            }
+            if occupied < best.0 {
+                best = (occupied, (left, top));
+            }
+        }
+        best.1
+    }
+
+    /// Draw a small example of a series, with its left edge at `pos` (pixels) and `height` pixels tall
+    fn draw_legend_sample(&mut self, command: &PlotCommand, series: &ResolvedStyle, pos: (f32, f32), height: f32) {
+        let (left, center) = pos;
+        let right = left + SAMPLE_WIDTH as f32;
+        let top = center - height / 4.;
+        let bottom = center + height / 4.;
+        match command {
+            PlotCommand::Scatter { .. } => {
+                let point = self.pixel_to_axis(((left + right) / 2., center));
+                self.draw_marker(point, series);
+            },
+            PlotCommand::Line { ls, .. } => {
+                let points = [
+                    Some(self.pixel_to_axis((left, center))),
+                    Some(self.pixel_to_axis((right, center))),
+                ];
+                self.draw_polyline(&points, ls, series.line_width, series.cap, series.color);
+            },
+            PlotCommand::ErrorBar { options, .. } => {
+                let x = (left + right) / 2.;
+                let butt = (CapStyle::Butt, CapStyle::Butt);
+                let mut painter = self.painter();
+                painter.draw_line((x, top), (x, bottom), series.line_width, butt, series.color);
+                let cap = options.cap_size;
+                if cap > 0. {
+                    painter.draw_line((x - cap, top), (x + cap, top), series.line_width, butt, series.color);
+                    painter.draw_line((x - cap, bottom), (x + cap, bottom), series.line_width, butt, series.color);
+                }
+                if options.markers {
+                    self.draw_marker(self.pixel_to_axis((x, center)), series);
+                }
+            },
+            PlotCommand::FillBetween { .. } | PlotCommand::FillBetweenX { .. } => {
+                self.painter().fill_rect((left, top, right, bottom), series.color);
+            },
+            PlotCommand::Hist { options, .. } if options.histtype != HistType::Step => {
+                self.painter().fill_rect((left, top, right, bottom), series.color);
+            },
+            PlotCommand::Bar { .. } | PlotCommand::Hist { .. } => {
+                let corners = [(left, bottom), (left, top), (right, top), (right, bottom), (left, bottom)];
+                self.painter().draw_polyline(&corners, series.line_width, CapStyle::Round, series.color);
+            },
+            _ => (),
+        }
+    }
+}
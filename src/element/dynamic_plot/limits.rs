@@ -0,0 +1,144 @@
+use super::{AxisScale, DynamicPlot, PlotCommand};
+
+/// How the limits of axes without `SetXLim` or `SetYLim` are fitted to the data, set with `PlotCommand::SetAutoLimits`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoLimits {
+    /// Fraction of the data's span along x, in scaled units, left empty on each side
+    pub x_margin: f32,
+    /// Fraction of the data's span along y, in scaled units, left empty on each side
+    pub y_margin: f32,
+    /// Stretch the x limits to reach zero, if the x scale can show it
+    pub x_include_zero: bool,
+    /// Stretch the y limits to reach zero, if the y scale can show it
+    pub y_include_zero: bool,
+}
+
+impl Default for AutoLimits {
+    fn default() -> Self {
+        Self {
+            x_margin: 0.03,
+            y_margin: 0.03,
+            x_include_zero: false,
+            y_include_zero: false,
+        }
+    }
+}
+
+/// The data one command draws, as the smallest and largest values along each axis which the scales can show
+struct Extent {
+    x: Option<(f32, f32)>,
+    y: Option<(f32, f32)>,
+    /// The data should fill the axes with no margin
+    tight: bool,
+}
+
+impl PlotCommand {
+    /// The data drawn by the command, or None if it draws none. `histogram` holds the bin edges and heights of
+    /// a `Hist` command.
+    fn data_extent(&self, histogram: Option<&(Vec<f32>, Vec<f32>)>, x_scale: AxisScale, y_scale: AxisScale) -> Option<Extent> {
+        let loose = |x, y| Some(Extent { x, y, tight: false });
+        match self {
+            PlotCommand::Scatter { xs, ys, .. } |
+            PlotCommand::Line { xs, ys, .. } => loose(x_scale.data_range(xs), y_scale.data_range(ys)),
+            PlotCommand::ErrorBar { xs, ys, options, .. } => {
+                let (x_values, y_values) = options.extents(xs, ys);
+                loose(x_scale.data_range(&x_values), y_scale.data_range(&y_values))
+            },
+            PlotCommand::FillBetween { xs, y1s, y2s, .. } => {
+                loose(x_scale.data_range(xs), y_scale.data_range(&[y1s.as_slice(), y2s.as_slice()].concat()))
+            },
+            PlotCommand::FillBetweenX { ys, x1s, x2s, .. } => {
+                loose(x_scale.data_range(&[x1s.as_slice(), x2s.as_slice()].concat()), y_scale.data_range(ys))
+            },
+            // Bars stand on zero, so it is kept in view
+            PlotCommand::Bar { edges, ys, .. } => {
+                loose(x_scale.data_range(edges), y_scale.data_range(&[ys.as_slice(), &[0.]].concat()))
+            },
+            PlotCommand::Hist { .. } => {
+                let (edges, heights) = histogram?;
+                if heights.is_empty() {
+                    return None;
+                }
+                loose(x_scale.data_range(edges), y_scale.data_range(&[heights.as_slice(), &[0.]].concat()))
+            },
+            // Grids fill their extent exactly
+            PlotCommand::ImShow { grid, .. } |
+            PlotCommand::Contour { grid, .. } => {
+                let (x0, x1, y0, y1) = grid.extent();
+                Some(Extent { x: x_scale.data_range(&[x0, x1]), y: y_scale.data_range(&[y0, y1]), tight: true })
+            },
+            // Text is placed in axis coordinates, so it does not depend on the limits
+            _ => None,
+        }
+    }
+}
+
+/// Smallest limits covering both of two sets of limits, either of which may be missing
+fn union_lim(a: Option<(f32, f32)>, b: Option<(f32, f32)>) -> Option<(f32, f32)> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.max(b.1))),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Limits fitting data on one axis. `loose` is widened by the margin and `tight` is not.
+fn fit(scale: AxisScale, loose: Option<(f32, f32)>, tight: Option<(f32, f32)>, margin: f32, include_zero: bool) -> Option<(f32, f32)> {
+    let lim = union_lim(loose.map(|lim| scale.widen(lim, margin.max(0.))), tight);
+    if include_zero && scale.contains(0.) {
+        lim.map(|lim| (lim.0.min(0.), lim.1.max(0.)))
+    } else {
+        lim
+    }
+}
+
+impl DynamicPlot {
+    /// Choose the axis scales and limits. Limits set by the commands are used as given, and the others are fitted
+    /// to the data of every command. The view chosen by panning and zooming replaces both.
+    pub(super) fn set_axis_limits(&mut self, commands: &[PlotCommand], histograms: &[Option<(Vec<f32>, Vec<f32>)>]) {
+        self.x_scale = AxisScale::Linear;
+        self.y_scale = AxisScale::Linear;
+        let mut auto = AutoLimits::default();
+        let mut x_set = None;
+        let mut y_set = None;
+        for command in commands {
+            match command {
+                PlotCommand::SetXScale { scale } => self.x_scale = *scale,
+                PlotCommand::SetYScale { scale } => self.y_scale = *scale,
+                PlotCommand::SetAutoLimits { limits } => auto = *limits,
+                PlotCommand::SetXLim { low, high } => x_set = Some((*low, *high)),
+                PlotCommand::SetYLim { low, high } => y_set = Some((*low, *high)),
+                _ => (),
+            }
+        }
+
+        let (mut x_loose, mut y_loose, mut x_tight, mut y_tight) = (None, None, None, None);
+        let extents = commands.iter().zip(histograms)
+            .filter_map(|(command, histogram)| command.data_extent(histogram.as_ref(), self.x_scale, self.y_scale));
+        for extent in extents {
+            if extent.tight {
+                x_tight = union_lim(x_tight, extent.x);
+                y_tight = union_lim(y_tight, extent.y);
+            } else {
+                x_loose = union_lim(x_loose, extent.x);
+                y_loose = union_lim(y_loose, extent.y);
+            }
+        }
+        let x_lim = x_set.or_else(|| fit(self.x_scale, x_loose, x_tight, auto.x_margin, auto.x_include_zero));
+        let y_lim = y_set.or_else(|| fit(self.y_scale, y_loose, y_tight, auto.y_margin, auto.y_include_zero));
+
+        // Limits the scale cannot show are replaced by the scale's defaults, and limits with no span are opened up
+        let fix = |scale: AxisScale, lim: Option<(f32, f32)>| match lim {
+            Some(lim) if !(scale.contains(lim.0) && scale.contains(lim.1)) => scale.default_lim(),
+            Some(lim) if lim.0 == lim.1 => scale.widen(lim, 0.),
+            Some(lim) => lim,
+            None => scale.default_lim(),
+        };
+        self.x_lim = fix(self.x_scale, x_lim);
+        self.y_lim = fix(self.y_scale, y_lim);
+        if let Some((x_lim, y_lim)) = self.view {
+            self.x_lim = x_lim;
+            self.y_lim = y_lim;
+        }
+    }
+}
@@ -0,0 +1,689 @@
+mod axes_style;
+mod colormap;
+mod contour;
+mod error_bar;
+mod fill;
+mod heatmap;
+mod hist;
+mod layout;
+mod legend;
+mod limits;
+mod scale;
+mod series_style;
+mod ticks;
+
+use image::{ImageBuffer, Rgba};
+
+use crate::{CapStyle, Color, Dasher, Dim, Painter};
+use crate::style::{Style, TextAlign, BLACK, WHITE};
+
+/// Factor the plot zooms by per pixel of wheel scrolling
+const WHEEL_ZOOM_RATE: f32 = 0.002;
+/// Largest distance in pixels from the mouse to a point for the point to count as hovered
+const HOVER_RADIUS: f32 = 12.;
+const TOOLTIP_OFFSET: i32 = 6;
+const TOOLTIP_PADDING: i32 = 3;
+
+use super::{Element, EventResponse, Gesture, Mouse};
+pub use axes_style::{AxesStyle, GridStyle, Spines, TickDirection};
+pub use colormap::Colormap;
+pub use contour::ContourStyle;
+pub use error_bar::{ErrorBarOptions, Errors};
+pub use heatmap::{GridData, ImageStyle, Interpolation};
+pub use hist::{histogram, Bins, HistOptions, HistType};
+pub use legend::LegendLoc;
+pub use limits::AutoLimits;
+pub use scale::AxisScale;
+pub use series_style::{Marker, SeriesStyle, COLOR_CYCLE};
+use series_style::ResolvedStyle;
+use layout::Margins;
+
+pub struct DynamicPlot {
+    pixels: image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+
+    x_lim: (f32, f32),
+    y_lim: (f32, f32),
+    x_scale: AxisScale,
+    y_scale: AxisScale,
+    margins: Margins,
+    axes_style: AxesStyle,
+
+    /// Limits chosen by panning and zooming. These replace the automatic or author-set limits until the view is reset.
+    view: Option<((f32, f32), (f32, f32))>,
+    /// Pointer id and last position of a drag in progress
+    drag: Option<(i32, (f32, f32))>,
+
+    /// Commands and style from the last call to `plot`, kept so the plot can be redrawn after panning or zooming
+    commands: Vec<PlotCommand>,
+    style: Option<Style>,
+    /// Bin edges and heights of each `Hist` command, in the same order as the commands. These do not depend on the
+    /// view, so they are binned once in `plot`.
+    histograms: Vec<Option<(Vec<f32>, Vec<f32>)>>,
+    /// Data point under the mouse
+    hover: Option<(f32, f32)>,
+    tooltips: bool,
+    crosshair: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+    DashDot,
+    /// Alternating on and off lengths in pixels, starting with an on length
+    Custom(Vec<f32>),
+}
+
+impl LineStyle {
+    /// Dash pattern in pixels, or an empty pattern for a solid line
+    fn pattern(&self) -> &[f32] {
+        match self {
+            LineStyle::Solid => &[],
+            LineStyle::Dashed => &[10., 6.],
+            LineStyle::Dotted => &[1., 5.],
+            LineStyle::DashDot => &[10., 4., 1., 4.],
+            LineStyle::Custom(pattern) => pattern,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PlotCommand {
+    Scatter{xs: Vec<f32>, ys: Vec<f32>, style: SeriesStyle},
+    ErrorBar{xs: Vec<f32>, ys: Vec<f32>, options: ErrorBarOptions, style: SeriesStyle},
+    /// Fill between two curves over x. Only runs of points where the mask is true are filled, if there is a mask.
+    FillBetween{xs: Vec<f32>, y1s: Vec<f32>, y2s: Vec<f32>, mask: Option<Vec<bool>>, style: SeriesStyle},
+    /// Fill between two curves over y, as `FillBetween` does over x
+    FillBetweenX{ys: Vec<f32>, x1s: Vec<f32>, x2s: Vec<f32>, mask: Option<Vec<bool>>, style: SeriesStyle},
+    Line{xs: Vec<f32>, ys: Vec<f32>, ls: LineStyle, style: SeriesStyle},
+    Bar{edges: Vec<f32>, ys: Vec<f32>, style: SeriesStyle},
+    /// Bin raw samples and draw the histogram
+    Hist{samples: Vec<f32>, bins: Bins, options: HistOptions, style: SeriesStyle},
+    /// Show values on a grid as an image, colored through a colormap
+    ImShow{grid: GridData, style: ImageStyle},
+    /// Draw lines where the grid crosses each level
+    Contour{grid: GridData, levels: Vec<f32>, style: ContourStyle},
+    SetXLim{low: f32, high: f32},
+    SetYLim{low: f32, high: f32},
+    /// Choose how limits which are not set are fitted to the data
+    SetAutoLimits{limits: AutoLimits},
+    SetXScale{scale: AxisScale},
+    SetYScale{scale: AxisScale},
+    SetXLabel{label: String},
+    SetYLabel{label: String},
+    SetTitle{title: String},
+    SetAxesStyle{style: AxesStyle},
+    /// Show a second y axis on the right. Its values are `forward` of the values on the left, and `inverse` converts back.
+    SecondaryYAxis{label: String, forward: fn(f32) -> f32, inverse: fn(f32) -> f32},
+    /// Label the series in the order they were given. Series labelled `None` are left out of the legend.
+    Legend{labels: Vec<Option<String>>, loc: LegendLoc},
+    Text{ x: f32, y: f32, text: String, va: TextAlign, ha: TextAlign },
+}
+
+impl PlotCommand {
+    /// Styling of the data series drawn by the command, if it draws one.
+    /// Series can appear in the legend and take colors from the color cycle.
+    fn series_style(&self) -> Option<&SeriesStyle> {
+        match self {
+            PlotCommand::Scatter { style, .. } |
+            PlotCommand::ErrorBar { style, .. } |
+            PlotCommand::FillBetween { style, .. } |
+            PlotCommand::FillBetweenX { style, .. } |
+            PlotCommand::Line { style, .. } |
+            PlotCommand::Bar { style, .. } |
+            PlotCommand::Hist { style, .. } => Some(style),
+            _ => None,
+        }
+    }
+
+    fn is_series(&self) -> bool {
+        self.series_style().is_some()
+    }
+}
+
+/// Resolve the style of every command, handing out colors from the cycle in order.
+/// Commands which are not series get a placeholder.
+fn resolve_series_styles(commands: &[PlotCommand], style: &Style) -> Vec<ResolvedStyle> {
+    let mut cycle_index = 0;
+    commands.iter().map(|command| {
+        let filled = matches!(command, PlotCommand::FillBetween { .. } | PlotCommand::FillBetweenX { .. });
+        match command.series_style() {
+            Some(series) => series.resolve(style, &mut cycle_index, filled),
+            None => SeriesStyle { color: Some(BLACK), ..Default::default() }.resolve(style, &mut cycle_index, false),
+        }
+    }).collect()
+}
+
+impl DynamicPlot {
+    pub fn new(rect: (Dim, Dim, Dim, Dim), window_width: u32, window_height: u32) -> Self {
+        let left = rect.0.to_pixel(window_width, window_height, true);
+        let top = rect.1.to_pixel(window_width, window_height, false);
+        let width = rect.2.to_pixel(window_width, window_height, true);
+        let height = rect.3.to_pixel(window_width, window_height, false);
+        Self {
+            pixels: ImageBuffer::new(width, height),
+            left,
+            top,
+            width,
+            height,
+            x_lim: (f32::NAN, f32::NAN),
+            y_lim: (f32::NAN, f32::NAN),
+            x_scale: AxisScale::Linear,
+            y_scale: AxisScale::Linear,
+            margins: Margins::default(),
+            axes_style: AxesStyle::default(),
+            view: None,
+            drag: None,
+            commands: Vec::new(),
+            style: None,
+            histograms: Vec::new(),
+            hover: None,
+            tooltips: true,
+            crosshair: false,
+        }
+    }
+
+    pub fn x_lim(&self) -> (f32, f32) {
+        self.x_lim
+    }
+
+    pub fn y_lim(&self) -> (f32, f32) {
+        self.y_lim
+    }
+
+    /// Left, top, width and height of the axes in window pixels. The margins around them are sized to fit the labels.
+    pub fn axes_rect(&self) -> (u32, u32, u32, u32) {
+        (self.left + self.axes_left(), self.top + self.axes_top(), self.axes_width(), self.axes_height())
+    }
+
+    /// Go back to the limits given by the plot commands
+    pub fn reset_view(&mut self) {
+        self.view = None;
+        self.redraw();
+    }
+
+    /// Show the value of the data point under the mouse. On by default.
+    pub fn set_tooltips(&mut self, tooltips: bool) {
+        self.tooltips = tooltips;
+    }
+
+    /// Draw lines through the data point under the mouse. Off by default.
+    pub fn set_crosshair(&mut self, crosshair: bool) {
+        self.crosshair = crosshair;
+    }
+
+    /// Data point under the mouse, if any
+    pub fn hovered_point(&self) -> Option<(f32, f32)> {
+        self.hover
+    }
+
+    /// Draw the plot. The commands are kept so that the plot can be redrawn when the user pans or zooms.
+    pub fn plot(&mut self, commands: Vec<PlotCommand>, style: &Style) {
+        self.histograms = commands.iter().map(|command| match command {
+            PlotCommand::Hist { samples, bins, options, .. } => Some(hist::histogram(samples, bins, options)),
+            _ => None,
+        }).collect();
+        self.commands = commands;
+        self.style = Some(style.clone());
+        self.hover = None;
+        self.redraw();
+    }
+
+    /// Draw the commands from the last call to `plot` again
+    fn redraw(&mut self) {
+        let Some(style) = self.style.take() else { return };
+        let commands = std::mem::take(&mut self.commands);
+        let histograms = std::mem::take(&mut self.histograms);
+        self.render(&commands, &histograms, &style);
+        self.commands = commands;
+        self.histograms = histograms;
+        self.style = Some(style);
+    }
+
+    fn render(&mut self, commands: &[PlotCommand], histograms: &[Option<(Vec<f32>, Vec<f32>)>], style: &Style) {
+        self.clear();
+        self.set_axis_limits(commands, histograms);
+        self.axes_style = commands.iter().rev().find_map(|command| match command {
+            PlotCommand::SetAxesStyle { style } => Some(style.clone()),
+            _ => None,
+        }).unwrap_or_default();
+        self.compute_layout(commands, style);
+        self.draw_images(commands);
+        self.draw_grid();
+        self.draw_axis();
+        self.draw_ticks(commands, style);
+        self.draw_labels(commands, style);
+        self.draw_colorbar(commands, style);
+        let series_styles = resolve_series_styles(commands, style);
+        for ((command, series), histogram) in commands.iter().zip(series_styles.iter()).zip(histograms) {
+            match command {
+                PlotCommand::Scatter { xs, ys, .. } => {
+                    for i in 0..xs.len() {
+                        let point = match self.data_to_axis((xs[i], ys[i])) {
+                            Ok(p) => p,
+                            Err(_) => continue
+                        };
+                        self.draw_marker(point, series);
+                    }
+                },
+                PlotCommand::Line { xs, ys, ls, .. } => {
+                    // Points outside the limits are kept, so that segments leaving the axes are cut at their
+                    // edges rather than dropped. Only points the scales cannot show break the line.
+                    let points = xs.iter().zip(ys.iter())
+                        .map(|(x, y)| Some(self.unprotected_data_to_axis((*x, *y))).filter(|p| p.0.is_finite() && p.1.is_finite()))
+                        .collect::<Vec<_>>();
+                    let pieces = self.dashed_pieces(&points, ls);
+                    let clip = self.axes_clip();
+                    let mut painter = self.painter();
+                    painter.set_clip(clip);
+                    for piece in pieces {
+                        painter.draw_polyline(&piece, series.line_width, series.cap, series.color);
+                    }
+                },
+                PlotCommand::Bar { edges, ys, .. } => {
+                    // Bars rise from zero, or from the bottom of the axes when zero cannot be shown
+                    let base = if self.y_scale.contains(0.) { 0. } else { self.y_lim.0 };
+                    let mut outline = vec![(edges[0], base)];
+                    for i in 0..(edges.len()-1) {
+                        outline.push((edges[i], ys[i]));
+                        outline.push((edges[i+1], ys[i]));
+                    }
+                    outline.push((edges[0], base));
+                    let points = outline.iter().map(|p| Some(self.unprotected_data_to_axis(*p))).collect::<Vec<_>>();
+                    self.draw_polyline(&points, &LineStyle::Solid, series.line_width, CapStyle::Round, series.color);
+                },
+                // Drawn before the axes
+                PlotCommand::ImShow { .. } => (),
+                // Filled bands are drawn before the axes, and the lines now
+                PlotCommand::Contour { grid, levels, style: contour } => self.draw_contour(grid, levels, contour, style),
+                PlotCommand::Hist { options, .. } => {
+                    if let Some((edges, heights)) = histogram {
+                        self.draw_hist(edges, heights, options.histtype, series);
+                    }
+                },
+                PlotCommand::SetXLabel { .. } => (),
+                PlotCommand::SetYLabel { .. } => (),
+                PlotCommand::SetTitle { .. } => (),
+                PlotCommand::SetAxesStyle { .. } => (),
+                PlotCommand::SecondaryYAxis { .. } => (),
+                PlotCommand::SetXLim { .. } => (),
+                PlotCommand::SetYLim { .. } => (),
+                PlotCommand::SetAutoLimits { .. } => (),
+                PlotCommand::SetXScale { .. } => (),
+                PlotCommand::SetYScale { .. } => (),
+                PlotCommand::ErrorBar { xs, ys, options, .. } => self.draw_error_bars(xs, ys, options, series),
+                PlotCommand::FillBetween { xs, y1s, y2s, mask, .. } => {
+                    let polygons = self.band_polygons(xs, y1s, y2s, mask.as_deref(), false);
+                    self.fill_polygons(&polygons, series.color);
+                },
+                PlotCommand::FillBetweenX { ys, x1s, x2s, mask, .. } => {
+                    let polygons = self.band_polygons(ys, x1s, x2s, mask.as_deref(), true);
+                    self.fill_polygons(&polygons, series.color);
+                },
+                // Drawn last, so the legend can avoid the data
+                PlotCommand::Legend { .. } => (),
+                PlotCommand::Text { x, y, text, va, ha } => {
+                    let (x, y) = self.axis_to_pixel((*x, *y));
+                    self.painter().draw_text(style, (x.round() as i32, y.round() as i32), text, BLACK, *va, *ha);
+                },
+            }
+        }
+        let legend = commands.iter().rev().find_map(|command| match command {
+            PlotCommand::Legend { labels, loc } => Some((labels, *loc)),
+            _ => None,
+        });
+        if let Some((labels, loc)) = legend {
+            self.draw_legend(commands, &series_styles, labels, loc, style);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.painter().fill(WHITE);
+    }
+
+    fn draw_axis(&mut self) {
+        let (left, right) = (self.axes_left() as i32, self.axes_right() as i32);
+        let (top, bottom) = (self.axes_top() as i32, self.axes_bottom() as i32);
+        let spines = self.axes_style.spines;
+        let mut painter = self.painter();
+        match spines {
+            Spines::Open => {
+                painter.draw_v_line(left, (top, bottom), BLACK);
+                painter.draw_h_line(bottom, (left, right - 1), BLACK);
+            },
+            Spines::Box => {
+                painter.draw_v_line(left, (top, bottom), BLACK);
+                painter.draw_v_line(right, (top, bottom), BLACK);
+                painter.draw_h_line(top, (left, right), BLACK);
+                painter.draw_h_line(bottom, (left, right), BLACK);
+            },
+            Spines::None => (),
+        }
+    }
+
+    /// Convert a point from data to axis coordinates, through the axis scales. Points outside the limits
+    /// give `Err` with their unclipped position, and points the scales cannot show give `Err` with NaN.
+    fn data_to_axis(&self, pos: (f32, f32)) -> Result<(f32, f32),(f32, f32)> {
+        let answer=(
+            self.x_scale.value_to_axis(pos.0, self.x_lim),
+            self.y_scale.value_to_axis(pos.1, self.y_lim),
+        );
+        if !answer.0.is_finite() || !answer.1.is_finite() {
+            return Err(answer);
+        }
+        if pos.0 < self.x_lim.0 || pos.0 > self.x_lim.1 || pos.1 < self.y_lim.0 || pos.1 > self.y_lim.1 {
+            return Err(answer);
+        }
+        Ok(answer)
+    }
+    
+    fn unprotected_data_to_axis(&self, pos: (f32, f32)) -> (f32,f32) {
+        match self.data_to_axis(pos) {
+            Ok(p) => p,
+            Err(p) => p,
+        }
+    }
+
+    fn axis_to_pixel(&self, pos: (f32, f32)) -> (f32, f32) {
+        (
+            self.axes_left() as f32 + pos.0 * self.axes_width() as f32,
+            self.axes_bottom() as f32 - pos.1 * self.axes_height() as f32,
+        )
+    }
+
+    fn axis_to_data(&self, pos: (f32, f32)) -> (f32, f32) {
+        (
+            self.x_scale.axis_to_value(pos.0, self.x_lim),
+            self.y_scale.axis_to_value(pos.1, self.y_lim),
+        )
+    }
+
+    fn pixel_to_axis(&self, pos: (f32, f32)) -> (f32, f32) {
+        (
+            (pos.0 - self.axes_left() as f32) / self.axes_width() as f32,
+            (self.axes_bottom() as f32 - pos.1) / self.axes_height() as f32,
+        )
+    }
+
+    /// Position of the mouse relative to the plot, in pixels
+    fn local_pixel(&self, mouse: Mouse) -> (f32, f32) {
+        (mouse.x as f32 - self.left as f32, mouse.y as f32 - self.top as f32)
+    }
+
+    /// Move the view so that the data follows a pointer which moved by (dx, dy) pixels
+    fn pan(&mut self, dx: f32, dy: f32) {
+        let shift = (
+            dx / self.axes_width() as f32,
+            -dy / self.axes_height() as f32,
+        );
+        let low = self.axis_to_data((-shift.0, -shift.1));
+        let high = self.axis_to_data((1. - shift.0, 1. - shift.1));
+        self.set_view((low.0, high.0), (low.1, high.1));
+    }
+
+    /// Magnify the view by `scale` about a point given in pixels
+    fn zoom(&mut self, center: (f32, f32), scale: f32) {
+        if !scale.is_finite() || scale <= 0. {
+            return;
+        }
+        let center = self.pixel_to_axis(center);
+        let low = self.axis_to_data((center.0 - center.0 / scale, center.1 - center.1 / scale));
+        let high = self.axis_to_data((center.0 + (1. - center.0) / scale, center.1 + (1. - center.1) / scale));
+        self.set_view((low.0, high.0), (low.1, high.1));
+    }
+
+    fn set_view(&mut self, x_lim: (f32, f32), y_lim: (f32, f32)) {
+        // Refuse views which have collapsed or overflowed
+        let valid = |lim: (f32, f32), scale: AxisScale| lim.0.is_finite() && lim.1.is_finite() && lim.0 < lim.1
+            && scale.contains(lim.0) && scale.contains(lim.1);
+        if !valid(x_lim, self.x_scale) || !valid(y_lim, self.y_scale) {
+            return;
+        }
+        self.x_lim = x_lim;
+        self.y_lim = y_lim;
+        self.view = Some((x_lim, y_lim));
+        self.redraw();
+    }
+
+    /// Find the data point nearest to a pixel, among the series which have points
+    fn nearest_point(&self, pixel: (f32, f32)) -> Option<(f32, f32)> {
+        let mut best = None;
+        let mut best_dist2 = HOVER_RADIUS * HOVER_RADIUS;
+        for command in &self.commands {
+            let (xs, ys) = match command {
+                PlotCommand::Scatter { xs, ys, .. } => (xs, ys),
+                PlotCommand::Line { xs, ys, .. } => (xs, ys),
+                PlotCommand::ErrorBar { xs, ys, .. } => (xs, ys),
+                _ => continue,
+            };
+            for (x, y) in xs.iter().zip(ys.iter()) {
+                let Ok(axis) = self.data_to_axis((*x, *y)) else { continue };
+                let point = self.axis_to_pixel(axis);
+                let dist2 = (point.0 - pixel.0).powi(2) + (point.1 - pixel.1).powi(2);
+                if dist2 < best_dist2 {
+                    best_dist2 = dist2;
+                    best = Some((*x, *y));
+                }
+            }
+        }
+        best
+    }
+
+    /// Draw the crosshair and tooltip for the hovered point onto the applet
+    fn draw_hover(&self, painter: &mut Painter, style: &Style) {
+        let Some(point) = self.hover else { return };
+        let Ok(axis) = self.data_to_axis(point) else { return };
+        let (x, y) = self.axis_to_pixel(axis);
+        let (x, y) = (self.left as i32 + x.round() as i32, self.top as i32 + y.round() as i32);
+
+        let axes_left = (self.left + self.axes_left()) as i32;
+        let axes_right = (self.left + self.axes_right()) as i32;
+        let axes_top = (self.top + self.axes_top()) as i32;
+        let axes_bottom = (self.top + self.axes_bottom()) as i32;
+        if self.crosshair {
+            let color = Color::from(style.highlight_color).faded(0.6).into();
+            painter.draw_h_line(y, (axes_left, axes_right - 1), color);
+            painter.draw_v_line(x, (axes_top, axes_bottom - 1), color);
+        }
+
+        if self.tooltips {
+            let text = format!("({}, {})", format_value(point.0), format_value(point.1));
+            let (text_width, text_height) = style.text_size(&text);
+            let (box_width, box_height) = (text_width as i32 + 2 * TOOLTIP_PADDING, text_height as i32 + 2 * TOOLTIP_PADDING);
+            // Put the tooltip up and to the right of the point, unless it would leave the axes
+            let mut left = x + TOOLTIP_OFFSET;
+            if left + box_width > axes_right {
+                left = x - TOOLTIP_OFFSET - box_width;
+            }
+            let mut top = y - TOOLTIP_OFFSET - box_height;
+            if top < axes_top {
+                top = y + TOOLTIP_OFFSET;
+            }
+            // A one pixel border around the centers of the outermost pixels, and a translucent white inside it
+            let (left, top) = (left as f32, top as f32);
+            let (right, bottom) = (left + box_width as f32 - 1., top + box_height as f32 - 1.);
+            painter.fill_rect((left + 0.5, top + 0.5, right - 0.5, bottom - 0.5), Rgba([255, 255, 255, 230]));
+            painter.draw_rect((left, top, right, bottom), 1., style.highlight_color);
+            painter.draw_text(
+                style, (left as i32 + TOOLTIP_PADDING, top as i32 + TOOLTIP_PADDING),
+                &text, BLACK, TextAlign::UpperLeft, TextAlign::UpperLeft
+            );
+        }
+    }
+
+    /// Draw onto the plot's own pixels
+    fn painter(&mut self) -> Painter<'_> {
+        let linear_blending = self.style.as_ref().is_some_and(|s| s.linear_blending);
+        let mut painter = Painter::new(&mut self.pixels);
+        painter.set_linear_blending(linear_blending);
+        painter
+    }
+
+    /// Draw a line through points given in axis coordinates. Segments touching a `None` point are skipped,
+    /// and the dash pattern carries on across the gap.
+    fn draw_polyline(&mut self, points: &[Option<(f32, f32)>], ls: &LineStyle, line_width: f32, cap: CapStyle, color: Rgba<u8>) {
+        for piece in self.dashed_pieces(points, ls) {
+            self.painter().draw_polyline(&piece, line_width, cap, color);
+        }
+    }
+
+    /// The solid pieces, in pixels, of a line through points given in axis coordinates. Segments touching a `None`
+    /// point are left out, and the dash pattern carries on across the gap.
+    fn dashed_pieces(&self, points: &[Option<(f32, f32)>], ls: &LineStyle) -> Vec<Vec<(f32, f32)>> {
+        let mut dasher = Dasher::new(ls.pattern());
+        let mut pieces = Vec::new();
+        for run in points.split(|p| p.is_none()) {
+            let run = run.iter().flatten().map(|p| self.axis_to_pixel(*p)).collect::<Vec<_>>();
+            if run.len() < 2 {continue;}
+            pieces.extend(dasher.dash(&run));
+        }
+        pieces
+    }
+
+    /// Draw a scatter marker centered on pos (axis coordinates)
+    fn draw_marker(&mut self, pos: (f32, f32), series: &ResolvedStyle) {
+        let size = series.marker_size;
+        let edge = Rgba([0, 0, 0, series.color.0[3]]);
+        // Snapped to the nearest pixel, so that markers of one kind all look alike
+        let center = self.axis_to_pixel(pos);
+        let center = (center.0.round(), center.1.round());
+        let mut painter = self.painter();
+        // Filled and outlined, given by the signed distance in pixels from the center offset (x, y), positive inside
+        let mut shape = |extent: f32, distance: &dyn Fn(f32, f32) -> f32| {
+            let bounds = (center.0 - extent - 1., center.1 - extent - 1., center.0 + extent + 1., center.1 + extent + 1.);
+            painter.fill_distance(bounds, series.color, |x, y| distance(x - center.0, y - center.1));
+            painter.stroke_distance(bounds, 1., edge, |x, y| distance(x - center.0, y - center.1));
+        };
+        match series.marker {
+            Marker::Circle => shape(size, &|x, y| size - x.hypot(y)),
+            Marker::Square => shape(size, &|x, y| size - x.abs().max(y.abs())),
+            Marker::Triangle => {
+                // Equilateral, pointing up, with the same area as a circle of radius size
+                let circumradius = size * 1.56;
+                let inradius = circumradius / 2.;
+                let normals = [(0., 1.), (0.866, -0.5), (-0.866, -0.5)];
+                shape(circumradius, &|x, y| {
+                    normals.iter().map(|n| inradius - (x * n.0 + y * n.1)).fold(f32::INFINITY, f32::min)
+                });
+            },
+            Marker::Cross | Marker::Plus => {
+                let arms: [(f32, f32); 2] = if series.marker == Marker::Plus {
+                    [(size, 0.), (0., size)]
+                } else {
+                    let diagonal = size * std::f32::consts::FRAC_1_SQRT_2;
+                    [(diagonal, diagonal), (diagonal, -diagonal)]
+                };
+                let width = (size / 2.).max(1.5);
+                for arm in arms {
+                    let a = (center.0 - arm.0, center.1 - arm.1);
+                    let b = (center.0 + arm.0, center.1 + arm.1);
+                    painter.draw_line(a, b, width, (CapStyle::Round, CapStyle::Round), series.color);
+                }
+            },
+        }
+    }
+}
+
+impl Element for DynamicPlot {
+    fn draw(&self, painter: &mut Painter, style: &Style) {
+        painter.draw_image((self.left as i32, self.top as i32), &self.pixels);
+        self.draw_hover(painter, style);
+    }
+
+    /// True if the mouse is over the axes
+    fn bbox(&self, mouse: Mouse) -> bool {
+        let (x, y) = self.local_pixel(mouse);
+        x >= self.axes_left() as f32 &&
+        x < self.axes_right() as f32 &&
+        y >= self.axes_top() as f32 &&
+        y < self.axes_bottom() as f32
+    }
+
+    fn mouse_button_down(&mut self, mouse: Mouse) -> EventResponse {
+        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        self.drag = Some((mouse.id, self.local_pixel(mouse)));
+        EventResponse::Responded
+    }
+
+    fn mouse_button_up(&mut self, mouse: Mouse) -> EventResponse {
+        let was_dragging = matches!(self.drag, Some((id, _)) if id == mouse.id);
+        if was_dragging {
+            self.drag = None;
+        }
+        if !was_dragging && !self.bbox(mouse) { return EventResponse::NoEvent }
+        EventResponse::Responded
+    }
+
+    fn mouse_move(&mut self, mouse: Mouse) -> EventResponse {
+        if let Some((id, last)) = self.drag {
+            if mouse.down && id == mouse.id {
+                let pos = self.local_pixel(mouse);
+                self.pan(pos.0 - last.0, pos.1 - last.1);
+                self.drag = Some((id, pos));
+                return EventResponse::Responded;
+            }
+        }
+        if !self.bbox(mouse) {
+            self.hover = None;
+            return EventResponse::NoEvent
+        }
+        self.hover = self.nearest_point(self.local_pixel(mouse));
+        EventResponse::Responded
+    }
+
+    fn pointer_cancel(&mut self, mouse: Mouse) {
+        if matches!(self.drag, Some((id, _)) if id == mouse.id) {
+            self.drag = None;
+        }
+    }
+
+    fn wheel(&mut self, mouse: Mouse, delta: f32) -> EventResponse {
+        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        self.zoom(self.local_pixel(mouse), (-delta * WHEEL_ZOOM_RATE).exp());
+        EventResponse::Responded
+    }
+
+    fn double_click(&mut self, mouse: Mouse) -> EventResponse {
+        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        self.reset_view();
+        EventResponse::Responded
+    }
+
+    fn wants_gestures(&self) -> bool {
+        true
+    }
+
+    fn gesture(&mut self, gesture: Gesture) -> EventResponse {
+        match gesture {
+            Gesture::Pan { dx, dy } => self.pan(dx, dy),
+            Gesture::Pinch { center, scale } => {
+                let center = (center.0 - self.left as f32, center.1 - self.top as f32);
+                self.zoom(center, scale);
+            },
+        }
+        EventResponse::Responded
+    }
+}
+
+/// Format a number for display with about four significant figures
+fn format_value(value: f32) -> String {
+    if value == 0. || !value.is_finite() {
+        return format!("{}", value);
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    if !(-3..5).contains(&magnitude) {
+        return format!("{:.3e}", value);
+    }
+    let decimals = (3 - magnitude).max(0) as usize;
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_owned()
+    } else {
+        text
+    }
+}
@@ -0,0 +1,204 @@
+use super::ticks::{nice_ticks, superscript, TickFormat};
+
+/// How data values along one axis are mapped onto the axis
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AxisScale {
+    #[default]
+    Linear,
+    /// Base-10 logarithm. Non-positive values are not drawn.
+    Log,
+    /// Linear within `linthresh` of zero and logarithmic outside, so data of both signs can span many decades
+    SymLog { linthresh: f32 },
+    /// Proportional to 1/x, as for wavelength against frequency. Non-positive values are not drawn.
+    Reciprocal,
+}
+
+impl AxisScale {
+    /// Position of a value in scaled units, or NaN if the value cannot be shown on this scale
+    pub(super) fn forward(&self, value: f32) -> f32 {
+        match self {
+            AxisScale::Linear => value,
+            AxisScale::Log => if value > 0. { value.log10() } else { f32::NAN },
+            AxisScale::SymLog { linthresh } => {
+                let linthresh = linthresh.abs();
+                if value.abs() <= linthresh {
+                    value / linthresh
+                } else {
+                    value.signum() * (1. + (value.abs() / linthresh).log10())
+                }
+            },
+            AxisScale::Reciprocal => if value > 0. { 1. / value } else { f32::NAN },
+        }
+    }
+
+    /// Value at a position in scaled units
+    pub(super) fn inverse(&self, scaled: f32) -> f32 {
+        match self {
+            AxisScale::Linear => scaled,
+            AxisScale::Log => 10f32.powf(scaled),
+            AxisScale::SymLog { linthresh } => {
+                let linthresh = linthresh.abs();
+                if scaled.abs() <= 1. {
+                    scaled * linthresh
+                } else {
+                    scaled.signum() * linthresh * 10f32.powf(scaled.abs() - 1.)
+                }
+            },
+            AxisScale::Reciprocal => 1. / scaled,
+        }
+    }
+
+    /// True if the value can be shown on this scale
+    pub(super) fn contains(&self, value: f32) -> bool {
+        self.forward(value).is_finite()
+    }
+
+    /// Fraction of the way along an axis with limits `lim` at which a value sits. NaN if the value cannot be shown.
+    pub(super) fn value_to_axis(&self, value: f32, lim: (f32, f32)) -> f32 {
+        let low = self.forward(lim.0);
+        let high = self.forward(lim.1);
+        (self.forward(value) - low) / (high - low)
+    }
+
+    /// Value at a fraction of the way along an axis with limits `lim`
+    pub(super) fn axis_to_value(&self, pos: f32, lim: (f32, f32)) -> f32 {
+        let low = self.forward(lim.0);
+        let high = self.forward(lim.1);
+        self.inverse(low + pos * (high - low))
+    }
+
+    /// Smallest and largest values which can be shown on this scale, or None if there are none
+    pub(super) fn data_range(&self, values: &[f32]) -> Option<(f32, f32)> {
+        values.iter()
+            .filter(|v| self.contains(**v))
+            .fold(None, |range, v| match range {
+                None => Some((*v, *v)),
+                Some((low, high)) => Some((f32::min(low, *v), f32::max(high, *v))),
+            })
+    }
+
+    /// Widen limits by `fraction` of their span on each side, measured in scaled units.
+    /// Limits with no span are opened up around their value so that the axis still has a size.
+    pub(super) fn widen(&self, lim: (f32, f32), fraction: f32) -> (f32, f32) {
+        let low = self.forward(lim.0);
+        let high = self.forward(lim.1);
+        let buffer = if high != low { (high - low).abs() * fraction } else { low.abs().max(1.) * 0.1 };
+        let (low, high) = if high >= low { (low - buffer, high + buffer) } else { (low + buffer, high - buffer) };
+        let widened = (self.inverse(low), self.inverse(high));
+        if widened.0.is_finite() && widened.1.is_finite() && self.contains(widened.0) && self.contains(widened.1) {
+            widened
+        } else {
+            lim
+        }
+    }
+
+    /// Limits used when there is nothing to show
+    pub(super) fn default_lim(&self) -> (f32, f32) {
+        match self {
+            AxisScale::Linear | AxisScale::SymLog { .. } => (-1., 1.),
+            AxisScale::Log | AxisScale::Reciprocal => (1., 10.),
+        }
+    }
+
+    /// Major and minor tick positions in data units between the limits, for an axis `length` pixels long
+    pub(super) fn ticks(&self, lim: (f32, f32), length: f32) -> (Vec<f32>, Vec<f32>) {
+        match self {
+            AxisScale::Linear => nice_ticks(lim, length),
+            AxisScale::Log => log_ticks(lim),
+            AxisScale::SymLog { linthresh } => symlog_ticks(lim, linthresh.abs()),
+            AxisScale::Reciprocal => reciprocal_ticks(lim),
+        }
+    }
+
+    /// Labels for the major ticks, and the offset or multiplier they share if there is one
+    pub(super) fn tick_labels(&self, majors: &[f32]) -> (Vec<String>, Option<String>) {
+        match self {
+            AxisScale::Linear | AxisScale::Reciprocal => {
+                let format = TickFormat::new(majors);
+                (majors.iter().map(|v| format.format(*v)).collect(), format.annotation())
+            },
+            AxisScale::Log | AxisScale::SymLog { .. } => (majors.iter().map(|v| format_power(*v)).collect(), None),
+        }
+    }
+}
+
+/// Most major ticks wanted on a log axis before decades are skipped
+const MAX_LOG_MAJORS: i32 = 8;
+
+/// Ticks at powers of ten, with minor ticks at 2..9 times each power.
+/// When the limits span less than about a decade the multiples are major ticks too.
+/// When the limits span many decades only every few decades get a major tick, and the rest become minor ticks.
+fn log_ticks(lim: (f32, f32)) -> (Vec<f32>, Vec<f32>) {
+    if !(lim.0 > 0. && lim.1 > lim.0) {
+        return (Vec::new(), Vec::new());
+    }
+    let (low, high) = ((lim.0 as f64).log10(), (lim.1 as f64).log10());
+    let first = low.floor() as i32;
+    let last = high.ceil() as i32;
+    let step = ((last - first) / MAX_LOG_MAJORS + 1).max(1);
+    let in_range = |v: f64| v >= lim.0 as f64 * (1. - 1e-6) && v <= lim.1 as f64 * (1. + 1e-6);
+
+    let mut majors = Vec::new();
+    let mut minors = Vec::new();
+    for n in first..=last {
+        let power = 10f64.powi(n);
+        if n.rem_euclid(step) == 0 {
+            if in_range(power) {
+                majors.push(power as f32);
+            }
+        } else if in_range(power) {
+            minors.push(power as f32);
+        }
+        if step == 1 {
+            minors.extend((2..=9).map(|m| m as f64 * power).filter(|v| in_range(*v)).map(|v| v as f32));
+        }
+    }
+    // Within about a decade there are too few powers of ten to read the axis by, so label the multiples too
+    if majors.len() < 2 {
+        majors.append(&mut minors);
+    }
+    (majors, minors)
+}
+
+/// Zero, plus log ticks on either side outside the linear region
+fn symlog_ticks(lim: (f32, f32), linthresh: f32) -> (Vec<f32>, Vec<f32>) {
+    let mut majors = Vec::new();
+    let mut minors = Vec::new();
+    if lim.1 > linthresh {
+        let (m, n) = log_ticks((lim.0.max(linthresh), lim.1));
+        majors.extend(m);
+        minors.extend(n);
+    }
+    if lim.0 < -linthresh {
+        let (m, n) = log_ticks((linthresh.max(-lim.1), -lim.0));
+        majors.extend(m.into_iter().map(|v| -v));
+        minors.extend(n.into_iter().map(|v| -v));
+    }
+    if lim.0 <= 0. && lim.1 >= 0. {
+        majors.push(0.);
+    }
+    (majors, minors)
+}
+
+/// Ticks at 1, 2 and 5 times powers of ten, which stay readable as 1/x compresses the large values together
+fn reciprocal_ticks(lim: (f32, f32)) -> (Vec<f32>, Vec<f32>) {
+    let (powers, multiples) = log_ticks(lim);
+    powers.into_iter().chain(multiples)
+        .partition(|v| {
+            let mantissa = (*v as f64 / 10f64.powf((*v as f64).log10().floor())).round();
+            mantissa == 1. || mantissa == 2. || mantissa == 5.
+        })
+}
+
+/// Write a signed power of ten as 10ⁿ, using superscript digits. Other values are written out.
+fn format_power(value: f32) -> String {
+    if value == 0. {
+        return "0".to_owned();
+    }
+    let exponent = value.abs().log10().round() as i32;
+    if (value.abs() / 10f32.powi(exponent) - 1.).abs() > 1e-4 {
+        return super::format_value(value);
+    }
+    let sign = if value < 0. { "-" } else { "" };
+    format!("{}10{}", sign, superscript(exponent))
+}
@@ -0,0 +1,85 @@
+use image::Rgba;
+
+use crate::{CapStyle, Color, Style};
+
+/// Colors given to series which do not choose their own, in order. These are matplotlib's "tab10" colors.
+pub const COLOR_CYCLE: [Rgba<u8>; 10] = [
+    Rgba([31, 119, 180, 255]),
+    Rgba([255, 127, 14, 255]),
+    Rgba([44, 160, 44, 255]),
+    Rgba([214, 39, 40, 255]),
+    Rgba([148, 103, 189, 255]),
+    Rgba([140, 86, 75, 255]),
+    Rgba([227, 119, 194, 255]),
+    Rgba([127, 127, 127, 255]),
+    Rgba([188, 189, 34, 255]),
+    Rgba([23, 190, 207, 255]),
+];
+
+/// Opacity of `FillBetween` when no alpha is given
+const FILL_ALPHA: f32 = 0.3;
+
+/// Shape drawn at each point of a scatter plot
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Marker {
+    #[default]
+    Circle,
+    Square,
+    Triangle,
+    /// Diagonal cross (x)
+    Cross,
+    /// Upright cross (+)
+    Plus,
+}
+
+/// Optional styling for a data series. Anything left as `None` falls back on the plot `Style`,
+/// and the color falls back on the next color of `COLOR_CYCLE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SeriesStyle {
+    /// Color of the series. `Style::highlight_color` is not used for series.
+    pub color: Option<Rgba<u8>>,
+    /// Opacity between 0 and 1
+    pub alpha: Option<f32>,
+    pub marker: Marker,
+    /// Marker radius in pixels
+    pub marker_size: Option<f32>,
+    /// Line width in pixels
+    pub line_width: Option<f32>,
+    /// How the ends of lines and dashes are drawn
+    pub cap: CapStyle,
+}
+
+/// Series style with every choice made
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct ResolvedStyle {
+    /// Color, with the opacity in the alpha channel
+    pub color: Rgba<u8>,
+    pub marker: Marker,
+    pub marker_size: f32,
+    pub line_width: f32,
+    pub cap: CapStyle,
+}
+
+impl SeriesStyle {
+    /// Fill in the missing choices. `cycle_index` counts the series which have already used a color from the cycle,
+    /// and is advanced if this one uses one too.
+    pub(super) fn resolve(&self, style: &Style, cycle_index: &mut usize, filled: bool) -> ResolvedStyle {
+        let color = match self.color {
+            Some(c) => c,
+            None => {
+                let c = COLOR_CYCLE[*cycle_index % COLOR_CYCLE.len()];
+                *cycle_index += 1;
+                c
+            }
+        };
+        let default_alpha = if filled { FILL_ALPHA } else { 1. };
+        let alpha = self.alpha.unwrap_or(default_alpha).clamp(0., 1.);
+        ResolvedStyle {
+            color: Color::from(color).faded(alpha).into(),
+            marker: self.marker,
+            marker_size: self.marker_size.unwrap_or(style.point_radius),
+            line_width: self.line_width.unwrap_or(style.line_width),
+            cap: self.cap,
+        }
+    }
+}
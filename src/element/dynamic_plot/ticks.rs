@@ -0,0 +1,381 @@
+use crate::style::{Style, TextAlign, BLACK};
+use crate::{CapStyle, Dasher};
+
+use super::layout::SecondaryAxis;
+use super::{AxisScale, DynamicPlot, GridStyle, PlotCommand, TickDirection};
+
+/// Length in pixels of major tick marks
+pub(super) const TICK_LENGTH: u32 = 5;
+/// Length in pixels of minor tick marks
+pub(super) const MINOR_TICK_LENGTH: u32 = 2;
+
+/// Smallest spacing in pixels wanted between major ticks on a linear axis
+const MIN_MAJOR_SPACING: f32 = 50.;
+/// Space in pixels kept between neighbouring tick labels
+const LABEL_GAP: i32 = 4;
+
+/// Major and minor ticks at round steps of 1, 2, 2.5 or 5 times a power of ten, with about one major tick
+/// per `MIN_MAJOR_SPACING` pixels of an axis `length` pixels long
+pub(super) fn nice_ticks(lim: (f32, f32), length: f32) -> (Vec<f32>, Vec<f32>) {
+    let (low, high) = (lim.0.min(lim.1) as f64, lim.0.max(lim.1) as f64);
+    if !low.is_finite() || !high.is_finite() {
+        return (Vec::new(), Vec::new());
+    }
+    if low == high {
+        return (vec![low as f32], Vec::new());
+    }
+    let target = (length / MIN_MAJOR_SPACING).floor().clamp(2., 10.) as f64;
+    let raw_step = (high - low) / target;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    // Candidate steps in increasing order, from a decade below the raw step to a decade above
+    let candidates = [0.1, 1., 10.].into_iter()
+        .flat_map(|decade| [(1., 5), (2., 4), (2.5, 5), (5., 5)].map(|(m, minor_count)| (m * decade * magnitude, minor_count)))
+        .collect::<Vec<_>>();
+
+    // Multiples of the step within the limits, allowing for rounding at the ends
+    let multiples = |step: f64| {
+        let slack = step * 1e-6;
+        ((low - slack) / step).ceil() as i64..=((high + slack) / step).floor() as i64
+    };
+    // Take the smallest step no finer than the raw step, unless that leaves fewer than two major ticks
+    let mut index = candidates.iter().position(|(step, _)| *step >= raw_step).unwrap_or(candidates.len() - 1);
+    while index > 0 && multiples(candidates[index].0).count() < 2 {
+        index -= 1;
+    }
+    let (step, minor_count) = candidates[index];
+    let majors = multiples(step).map(|k| (k as f64 * step) as f32).collect();
+    let minor_step = step / minor_count as f64;
+    let minors = multiples(minor_step)
+        .filter(|k| k % minor_count != 0)
+        .map(|k| (k as f64 * minor_step) as f32)
+        .collect();
+    (majors, minors)
+}
+
+/// Labels for a set of ticks which share one precision. When the values would need many digits they are
+/// written relative to an offset and divided by a power of ten, which are shown once in the annotation.
+pub(super) struct TickFormat {
+    offset: f64,
+    exponent: i32,
+    decimals: usize,
+}
+
+impl TickFormat {
+    pub(super) fn new(values: &[f32]) -> Self {
+        let values = values.iter().map(|v| *v as f64).filter(|v| v.is_finite()).collect::<Vec<_>>();
+        let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if values.is_empty() {
+            return Self { offset: 0., exponent: 0, decimals: 0 };
+        }
+
+        // Use an offset when the values are large compared with their spread
+        let span = high - low;
+        let offset = if span > 0. && low.abs().max(high.abs()) / span >= 1e4 {
+            let unit = 10f64.powf(span.log10().ceil());
+            (low / unit).floor() * unit
+        } else {
+            0.
+        };
+
+        // Use a multiplier when what is left is very large or very small
+        let largest = values.iter().map(|v| (v - offset).abs()).fold(0., f64::max);
+        let exponent = if largest > 0. {
+            let magnitude = largest.log10().floor() as i32;
+            if !(-2..4).contains(&magnitude) { magnitude } else { 0 }
+        } else {
+            0
+        };
+
+        // Fewest decimals which write every value exactly, to the precision the ticks were placed with.
+        // Ticks far from zero only hold a few digits as f32, so rounding error at that level is allowed too.
+        let scaled = values.iter().map(|v| (v - offset) / 10f64.powi(exponent)).collect::<Vec<_>>();
+        let mut sorted = scaled.clone();
+        sorted.sort_by(f64::total_cmp);
+        let spacing = sorted.windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|d| *d > 0.)
+            .fold(f64::INFINITY, f64::min);
+        let spacing = if spacing.is_finite() { spacing } else { largest.max(1.) / 10f64.powi(exponent) };
+        let f32_precision = 4. * f32::EPSILON as f64 * low.abs().max(high.abs()) / 10f64.powi(exponent);
+        let tolerance = f64::max(spacing * 1e-3, f32_precision);
+        let decimals = (0..=8usize).find(|d| {
+            let unit = 10f64.powi(*d as i32);
+            scaled.iter().all(|v| ((v * unit).round() / unit - v).abs() < tolerance)
+        }).unwrap_or(8);
+
+        Self { offset, exponent, decimals }
+    }
+
+    pub(super) fn format(&self, value: f32) -> String {
+        let scaled = (value as f64 - self.offset) / 10f64.powi(self.exponent);
+        let text = format!("{:.*}", self.decimals, scaled);
+        // Avoid writing zero as -0
+        if text.trim_start_matches('-').chars().all(|c| c == '0' || c == '.') {
+            text.trim_start_matches('-').to_owned()
+        } else {
+            text
+        }
+    }
+
+    /// The multiplier and offset shared by every label, if there are any
+    pub(super) fn annotation(&self) -> Option<String> {
+        let mut text = String::new();
+        if self.exponent != 0 {
+            text.push_str(&format!("×10{}", superscript(self.exponent)));
+        }
+        if self.offset != 0. {
+            // The offset is a round number, so the shortest form of it is also the neatest
+            let offset = self.offset as f32;
+            let sign = if offset > 0. { "+" } else { "" };
+            if offset.abs() >= 1e5 || offset.abs() < 1e-3 {
+                text.push_str(&format!("{}{:e}", sign, offset));
+            } else {
+                text.push_str(&format!("{}{}", sign, offset));
+            }
+        }
+        if text.is_empty() { None } else { Some(text) }
+    }
+}
+
+/// Write an integer in superscript digits
+pub(super) fn superscript(n: i32) -> String {
+    n.to_string().chars().map(|c| match c {
+        '-' => '⁻',
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        _ => '⁹',
+    }).collect()
+}
+
+/// A box in pixels around a piece of text, as (left, top, right, bottom)
+type TextBox = (i32, i32, i32, i32);
+
+/// True if two boxes overlap or come within `LABEL_GAP` of each other
+fn boxes_collide(a: TextBox, b: TextBox) -> bool {
+    a.0 < b.2 + LABEL_GAP && b.0 < a.2 + LABEL_GAP && a.1 < b.3 + LABEL_GAP && b.1 < a.3 + LABEL_GAP
+}
+
+/// Smallest `n` such that labelling every `n`-th tick leaves no two labels colliding
+pub(super) fn label_stride(boxes: &[TextBox]) -> usize {
+    (1..boxes.len().max(1)).find(|stride| {
+        boxes.iter().step_by(*stride).collect::<Vec<_>>().windows(2).all(|pair| !boxes_collide(*pair[0], *pair[1]))
+    }).unwrap_or(boxes.len().max(1))
+}
+
+/// Ticks of one axis, placed for the current layout
+pub(super) struct AxisTicks {
+    /// Pixel position along the axis and label of each major tick, in order along the axis
+    pub majors: Vec<(u32, String)>,
+    /// Pixel position along the axis of each minor tick
+    pub minors: Vec<u32>,
+    /// Offset or multiplier shared by the labels
+    pub annotation: Option<String>,
+}
+
+impl DynamicPlot {
+    pub(super) fn x_ticks(&self) -> AxisTicks {
+        let (mut majors, minors) = self.x_scale.ticks(self.x_lim, self.axes_width() as f32);
+        majors.sort_by(f32::total_cmp);
+        let (labels, annotation) = self.x_scale.tick_labels(&majors);
+        let position = |v: &f32| self.axis_to_pixel((self.x_scale.value_to_axis(*v, self.x_lim), 0.)).0.round() as u32;
+        AxisTicks {
+            majors: majors.iter().map(position).zip(labels).collect(),
+            minors: minors.iter().map(position).collect(),
+            annotation,
+        }
+    }
+
+    pub(super) fn y_ticks(&self) -> AxisTicks {
+        self.vertical_ticks(self.y_scale, self.y_lim)
+    }
+
+    /// Ticks for values between `lim` on `scale`, running up the height of the axes
+    pub(super) fn vertical_ticks(&self, scale: AxisScale, lim: (f32, f32)) -> AxisTicks {
+        let (mut majors, minors) = scale.ticks(lim, self.axes_height() as f32);
+        majors.sort_by(f32::total_cmp);
+        let (labels, annotation) = scale.tick_labels(&majors);
+        let position = |v: &f32| self.axis_to_pixel((0., scale.value_to_axis(*v, lim))).1.round() as u32;
+        AxisTicks {
+            majors: majors.iter().map(position).zip(labels).collect(),
+            minors: minors.iter().map(position).collect(),
+            annotation,
+        }
+    }
+
+    /// Ticks at round values of the secondary axis, placed where the primary values they convert from are
+    pub(super) fn secondary_ticks(&self, axis: &SecondaryAxis) -> AxisTicks {
+        let ends = ((axis.forward)(self.y_lim.0), (axis.forward)(self.y_lim.1));
+        let lim = (ends.0.min(ends.1), ends.0.max(ends.1));
+        let (mut majors, minors) = self.y_scale.ticks(lim, self.axes_height() as f32);
+        majors.sort_by(f32::total_cmp);
+        let (labels, annotation) = self.y_scale.tick_labels(&majors);
+        let position = |v: &f32| self.axis_to_pixel((0., self.y_scale.value_to_axis((axis.inverse)(*v), self.y_lim))).1;
+        AxisTicks {
+            majors: majors.iter().map(position).zip(labels)
+                .filter(|(y, _)| y.is_finite())
+                .map(|(y, label)| (y.round() as u32, label))
+                .collect(),
+            minors: minors.iter().map(position).filter(|y| y.is_finite()).map(|y| y.round() as u32).collect(),
+            annotation,
+        }
+    }
+
+    /// Draw grid lines across the axes at the tick positions
+    pub(super) fn draw_grid(&mut self) {
+        let (left, right) = (self.axes_left() as f32, self.axes_right() as f32);
+        let (top, bottom) = (self.axes_top() as f32, self.axes_bottom() as f32);
+        let x_ticks = self.x_ticks();
+        let y_ticks = self.y_ticks();
+        let grids = [
+            (self.axes_style.minor_grid.clone(), x_ticks.minors.clone(), y_ticks.minors.clone()),
+            (
+                self.axes_style.major_grid.clone(),
+                x_ticks.majors.iter().map(|(x, _)| *x).collect(),
+                y_ticks.majors.iter().map(|(y, _)| *y).collect(),
+            ),
+        ];
+        for (grid, xs, ys) in grids {
+            let Some(grid) = grid else { continue };
+            for x in xs {
+                self.draw_grid_line((x as f32, bottom), (x as f32, top), &grid);
+            }
+            for y in ys {
+                self.draw_grid_line((left, y as f32), (right, y as f32), &grid);
+            }
+        }
+    }
+
+    /// Draw one grid line between two points in pixels
+    fn draw_grid_line(&mut self, a: (f32, f32), b: (f32, f32), grid: &GridStyle) {
+        let color = grid.blended_color();
+        for piece in Dasher::new(grid.ls.pattern()).dash(&[a, b]) {
+            self.painter().draw_polyline(&piece, grid.line_width, CapStyle::Butt, color);
+        }
+    }
+
+    /// Draw a tick mark at `position` along an axis which crosses the other direction at `from`. The mark points
+    /// `length` pixels into the axes, which lie in the direction of `into_axes`, unless ticks point out.
+    pub(super) fn draw_tick(&mut self, position: u32, from: u32, length: u32, into_axes: i32, vertical: bool) {
+        let outward = self.axes_style.tick_direction == TickDirection::Out;
+        let sign = if outward { -into_axes } else { into_axes };
+        let (position, from) = (position as i32, from as i32);
+        let end = from + sign * length as i32;
+        if vertical {
+            self.painter().draw_v_line(position, (from, end), BLACK);
+        } else {
+            // The end is left off when the mark points right
+            let span = if end > from { (from, end - 1) } else { (end, from) };
+            self.painter().draw_h_line(position, span, BLACK);
+        }
+    }
+
+    pub(super) fn draw_ticks(&mut self, commands: &[PlotCommand], style: &Style) {
+        let (left, right) = (self.axes_left(), self.axes_right());
+        let (top, bottom) = (self.axes_top(), self.axes_bottom());
+        let label_offset = self.tick_label_offset();
+
+        if self.axes_style.show_x {
+            let ticks = self.x_ticks();
+            for (x, _) in &ticks.majors {
+                self.draw_tick(*x, bottom, TICK_LENGTH, -1, true);
+            }
+            for x in &ticks.minors {
+                self.draw_tick(*x, bottom, MINOR_TICK_LENGTH, -1, true);
+            }
+
+            // Labels are thinned out evenly until they fit
+            let label_top = bottom + label_offset;
+            let stride = label_stride(&self.x_label_boxes(&ticks, label_top, style));
+            for (x, label) in ticks.majors.iter().step_by(stride) {
+                self.painter().draw_text(style, (*x as i32, label_top as i32), label, BLACK, TextAlign::UpperLeft, TextAlign::Center);
+            }
+            // Shared offsets and multipliers go at the far end of the axis, inside the axes
+            if let Some(annotation) = ticks.annotation {
+                self.painter().draw_text(style, (right as i32 - 2, bottom as i32 - 2), &annotation, BLACK, TextAlign::LowerRight, TextAlign::LowerRight);
+            }
+        }
+
+        if self.axes_style.show_y {
+            let ticks = self.y_ticks();
+            for (y, _) in &ticks.majors {
+                self.draw_tick(*y, left, TICK_LENGTH, 1, false);
+            }
+            for y in &ticks.minors {
+                self.draw_tick(*y, left, MINOR_TICK_LENGTH, 1, false);
+            }
+            let label_right = left.saturating_sub(label_offset);
+            let stride = label_stride(&self.y_label_boxes(&ticks, label_right, false, style));
+            for (y, label) in ticks.majors.iter().step_by(stride) {
+                self.painter().draw_text(style, (label_right as i32, *y as i32), label, BLACK, TextAlign::Center, TextAlign::LowerRight);
+            }
+            if let Some(annotation) = ticks.annotation {
+                self.painter().draw_text(style, (left as i32 + 4, top as i32 + 2), &annotation, BLACK, TextAlign::UpperLeft, TextAlign::UpperLeft);
+            }
+        }
+
+        if let Some(axis) = self.secondary_axis(commands) {
+            let ticks = self.secondary_ticks(&axis);
+            self.painter().draw_v_line(right as i32, (top as i32, bottom as i32), BLACK);
+            for (y, _) in &ticks.majors {
+                self.draw_tick(*y, right, TICK_LENGTH, -1, false);
+            }
+            for y in &ticks.minors {
+                self.draw_tick(*y, right, MINOR_TICK_LENGTH, -1, false);
+            }
+            let label_left = right + label_offset;
+            let stride = label_stride(&self.y_label_boxes(&ticks, label_left, true, style));
+            for (y, label) in ticks.majors.iter().step_by(stride) {
+                self.painter().draw_text(style, (label_left as i32, *y as i32), label, BLACK, TextAlign::Center, TextAlign::UpperLeft);
+            }
+            if let Some(annotation) = ticks.annotation {
+                self.painter().draw_text(style, (right as i32 - 4, top as i32 + 2), &annotation, BLACK, TextAlign::UpperLeft, TextAlign::LowerRight);
+            }
+        }
+    }
+
+    /// Boxes around the labels of the horizontal axis, which hang down from `edge`
+    pub(super) fn x_label_boxes(&self, ticks: &AxisTicks, edge: u32, style: &Style) -> Vec<TextBox> {
+        ticks.majors.iter().map(|(x, label)| {
+            let (w, h) = style.text_size(label);
+            (*x as i32 - w as i32 / 2, edge as i32, *x as i32 + w as i32 / 2, (edge + h) as i32)
+        }).collect()
+    }
+
+    /// Boxes around the labels of a vertical axis, which run leftward from `edge`, or rightward if `rightward` is true
+    pub(super) fn y_label_boxes(&self, ticks: &AxisTicks, edge: u32, rightward: bool, style: &Style) -> Vec<TextBox> {
+        ticks.majors.iter().map(|(y, label)| {
+            let (w, h) = style.text_size(label);
+            let (y, h) = (*y as i32, h as i32);
+            if rightward {
+                (edge as i32, y - h / 2, (edge + w) as i32, y + h / 2)
+            } else {
+                (edge as i32 - w as i32, y - h / 2, edge as i32, y + h / 2)
+            }
+        }).collect()
+    }
+
+    /// Boxes around every major tick label of the primary and secondary axes, whether or not it is thinned out
+    #[cfg(test)]
+    pub(crate) fn tick_label_boxes(&self, style: &Style) -> Vec<TextBox> {
+        let label_offset = self.tick_label_offset();
+        let mut boxes = Vec::new();
+        if self.axes_style.show_x {
+            boxes.extend(self.x_label_boxes(&self.x_ticks(), self.axes_bottom() + label_offset, style));
+        }
+        if self.axes_style.show_y {
+            boxes.extend(self.y_label_boxes(&self.y_ticks(), self.axes_left().saturating_sub(label_offset), false, style));
+        }
+        if let Some(axis) = self.secondary_axis(&self.commands) {
+            boxes.extend(self.y_label_boxes(&self.secondary_ticks(&axis), self.axes_right() + label_offset, true, style));
+        }
+        boxes
+    }
+}
@@ -1,15 +1,23 @@
 mod slider;
 mod dynamic_plot;
 mod button;
+mod canvas;
+mod registry;
+
+use std::any::Any;
 
-use image::ImageBuffer;
 pub use slider::*;
 pub use dynamic_plot::*;
 pub use button::*;
-use super::{Callback, Style};
+pub use canvas::*;
+pub use registry::*;
+use super::{Callback, Painter, Style};
 
-pub trait Element {
-    fn draw(&self, pixels: &mut ImageBuffer<image::Rgba<u8>, Vec<u8>>, style: &Style);
+pub trait Element: Any {
+    /// Called when the element is added to a registry, with its handle there.
+    /// Elements which place callbacks should remember it.
+    fn attach(&mut self, _id: ElementId<Self>) where Self: Sized {}
+    fn draw(&self, painter: &mut Painter, style: &Style);
     fn tick(&mut self) -> Option<Callback> { None }
     /// Returns true if the mouse is inside the element
     fn bbox(&self, mouse: Mouse) -> bool;
@@ -25,6 +33,37 @@ pub trait Element {
         if !self.bbox(mouse) { return EventResponse::NoEvent }
         EventResponse::Responded
     }
+    /// Scrolling with the mouse wheel. `delta` is in pixels, positive when scrolling down.
+    fn wheel(&mut self, _mouse: Mouse, _delta: f32) -> EventResponse {
+        EventResponse::NoEvent
+    }
+    fn double_click(&mut self, _mouse: Mouse) -> EventResponse {
+        EventResponse::NoEvent
+    }
+    /// Called when a pointer which this element captured is interrupted, or becomes part of a gesture.
+    /// Any drag in progress should be abandoned.
+    fn pointer_cancel(&mut self, _mouse: Mouse) {}
+    /// Returns true if the element would like two-pointer gestures
+    fn wants_gestures(&self) -> bool { false }
+    /// Gestures are only sent to elements which want them
+    fn gesture(&mut self, _gesture: Gesture) -> EventResponse {
+        EventResponse::NoEvent
+    }
+    /// Returns true if the element can take keyboard focus
+    fn focusable(&self) -> bool { false }
+    /// Called when the element gains or loses keyboard focus
+    fn set_focus(&mut self, _focused: bool) {}
+    /// Key events are only sent to the focused element
+    fn key_down(&mut self, _key: Key) -> EventResponse {
+        EventResponse::NoEvent
+    }
+    fn key_up(&mut self, _key: Key) -> EventResponse {
+        EventResponse::NoEvent
+    }
+    /// Text typed while the element is focused
+    fn text_input(&mut self, _text: &str) -> EventResponse {
+        EventResponse::NoEvent
+    }
 }
 
 /// Enum used to determine whether a given element is affected by an event
@@ -37,9 +76,75 @@ pub enum EventResponse {
     PlaceCallback(Callback),
 }
 
+/// Pointer id used for events coming from the mouse methods of `Applet`
+pub const MOUSE_POINTER: i32 = 1;
+
+/// A mouse, pen or touch pointer
 #[derive(Clone, Copy, Debug)]
 pub struct Mouse {
     pub x: u32,
     pub y: u32,
-    pub down: bool
-}
\ No newline at end of file
+    pub down: bool,
+    /// Distinguishes simultaneous pointers, e.g. fingers on a touch screen
+    pub id: i32,
+}
+
+/// Gesture made with two pointers on one element
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    /// The pointers moved together by (dx, dy) pixels
+    Pan { dx: f32, dy: f32 },
+    /// The pointers moved apart (scale > 1) or closer together (scale < 1) about center, in pixels
+    Pinch { center: (f32, f32), scale: f32 },
+}
+
+/// A key on the keyboard, named after the `key` field of a DOM `KeyboardEvent`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    ArrowDown,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Space,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Shift,
+    Character(char),
+    Other,
+}
+
+impl Key {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "ArrowLeft" | "Left" => Key::ArrowLeft,
+            "ArrowRight" | "Right" => Key::ArrowRight,
+            "ArrowUp" | "Up" => Key::ArrowUp,
+            "ArrowDown" | "Down" => Key::ArrowDown,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "Enter" => Key::Enter,
+            " " | "Spacebar" => Key::Space,
+            "Tab" => Key::Tab,
+            "Escape" | "Esc" => Key::Escape,
+            "Backspace" => Key::Backspace,
+            "Delete" | "Del" => Key::Delete,
+            "Shift" => Key::Shift,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Key::Character(c),
+                    _ => Key::Other,
+                }
+            }
+        }
+    }
+}
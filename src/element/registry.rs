@@ -0,0 +1,129 @@
+use std::{any::Any, fmt, hash::{Hash, Hasher}, marker::PhantomData, sync::atomic::{AtomicUsize, Ordering}};
+
+use super::Element;
+
+/// Number given to the next registry, so that handles from different registries never match
+static NEXT_REGISTRY: AtomicUsize = AtomicUsize::new(0);
+
+/// Typed handle to an element owned by an `Elements` registry. Handles are cheap to copy and compare,
+/// so they can be stored by the applet and matched against callbacks.
+pub struct ElementId<T> {
+    registry: usize,
+    index: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ElementId<T> {
+    fn new(registry: usize, index: usize) -> Self {
+        Self {
+            registry,
+            index,
+            marker: PhantomData,
+        }
+    }
+
+    /// Position of the element in its registry
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T> Clone for ElementId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ElementId<T> {}
+
+impl<T> PartialEq for ElementId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.registry == other.registry && self.index == other.index
+    }
+}
+
+impl<T> Eq for ElementId<T> {}
+
+impl<T> Hash for ElementId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.registry.hash(state);
+        self.index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for ElementId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ElementId({}, {})", self.registry, self.index)
+    }
+}
+
+/// Owns the elements of an applet. Elements are dispatched events and drawn in the order they were added.
+pub struct Elements {
+    /// Number of this registry, which its handles carry
+    id: usize,
+    elements: Vec<Box<dyn Element>>,
+}
+
+impl Default for Elements {
+    fn default() -> Self {
+        Self {
+            id: NEXT_REGISTRY.fetch_add(1, Ordering::Relaxed),
+            elements: Vec::new(),
+        }
+    }
+}
+
+impl Elements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Panic if `id` is a handle from another registry
+    fn check<T>(&self, id: ElementId<T>) {
+        assert_eq!(id.registry, self.id, "Element id does not belong to this registry");
+    }
+
+    /// Take ownership of an element and return a handle to it
+    pub fn add<T: Element>(&mut self, mut element: T) -> ElementId<T> {
+        let id = ElementId::new(self.id, self.elements.len());
+        element.attach(id);
+        self.elements.push(Box::new(element));
+        id
+    }
+
+    pub fn get<T: Element>(&self, id: ElementId<T>) -> &T {
+        self.check(id);
+        let element: &dyn Any = self.elements[id.index].as_ref();
+        element.downcast_ref().expect("Element id does not belong to this registry")
+    }
+
+    pub fn get_mut<T: Element>(&mut self, id: ElementId<T>) -> &mut T {
+        self.check(id);
+        let element: &mut dyn Any = self.elements[id.index].as_mut();
+        element.downcast_mut().expect("Element id does not belong to this registry")
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub(crate) fn get_dyn(&self, index: usize) -> &dyn Element {
+        self.elements[index].as_ref()
+    }
+
+    pub(crate) fn get_dyn_mut(&mut self, index: usize) -> &mut dyn Element {
+        self.elements[index].as_mut()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &dyn Element> {
+        self.elements.iter().map(|e| e.as_ref())
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Element>> {
+        self.elements.iter_mut()
+    }
+}
@@ -1,10 +1,10 @@
-use image::Rgba;
-use crate::{blend_color, style::{BLACK, WHITE}, Dim, Style};
-use super::{Element, EventResponse, Mouse};
+use crate::{style::{BLACK, WHITE}, Dim, Painter, Style};
+use super::{Element, EventResponse, Key, Mouse};
 
 const SLIDER_RADIUS: i32 = 6;
 const THICKNESS: i32 = 1;
 const TEXT_BUFFER: i32 = 8;
+const FOCUS_GAP: i32 = 3;
 
 pub enum SliderType {
     Float(u32),
@@ -21,7 +21,9 @@ pub struct Slider {
     pos: f32,
     start: f32,
     end: f32,
-    selected: bool,
+    /// Id of the pointer dragging the slider
+    selected: Option<i32>,
+    focused: bool,
 }
 
 impl Slider {
@@ -37,7 +39,8 @@ impl Slider {
             pos: (data[2] - data[0]) / (data[1] - data[0]),
             start: data[0],
             end: data[1],
-            selected: false,
+            selected: None,
+            focused: false,
         }
     }
 
@@ -51,35 +54,38 @@ impl Slider {
             SliderType::Int => f_out.round(),
         }.clamp(self.start, self.end)
     }
+
+    /// Amount the value changes by for one arrow key press
+    fn step(&self) -> f32 {
+        match self.slider_type {
+            SliderType::Float(d) => 10f32.powi(-(d as i32)),
+            SliderType::Int => 1.,
+        }
+    }
+
+    fn set_value(&mut self, value: f32) {
+        self.pos = ((value - self.start) / (self.end - self.start)).clamp(0., 1.);
+    }
 }
 
 impl Element for Slider {
-    fn draw(&self, pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, style: &Style) {
-        for i in -self.width/2..=self.width/2 {
-            pixels[((self.x as i32 + i) as u32, self.y)] = Rgba([0,0,0,255]);
-        }
+    fn draw(&self, painter: &mut Painter, style: &Style) {
+        painter.draw_h_line(self.y as i32, (self.x as i32 - self.width/2, self.x as i32 + self.width/2), BLACK);
         let dx = (self.pos * self.width as f32) as i32 - self.width / 2;
-        for i in -(SLIDER_RADIUS+THICKNESS/2)..=SLIDER_RADIUS+THICKNESS/2 {
-            for j in -(SLIDER_RADIUS+THICKNESS/2)..=SLIDER_RADIUS+THICKNESS/2 {
-                let dist = ((i * i + j * j) as f32).sqrt();
-                let v = ((SLIDER_RADIUS as f32 - dist)/THICKNESS as f32).abs().min(1.);
-                if v >= 0. {
-                    if self.x as i32 + i + dx < 0 {continue;}
-                    if self.x as i32 + i + dx >= pixels.width() as i32 {continue;}
-                    if self.y as i32 + j < 0 {continue;}
-                    if self.y as i32 + j >= pixels.height() as i32 {continue;}
-                    pixels[((self.x as i32 + i + dx) as u32, (self.y as i32 + j) as u32)] = blend_color(WHITE, style.highlight_color, v);
-                }
-            }
+        let center = ((self.x as i32 + dx) as f32, self.y as f32);
+        painter.fill_circle(center, (SLIDER_RADIUS + THICKNESS) as f32, WHITE);
+        painter.draw_circle(center, SLIDER_RADIUS as f32, THICKNESS as f32, style.highlight_color);
+        if self.focused {
+            painter.draw_circle(center, (SLIDER_RADIUS + FOCUS_GAP) as f32, 1., style.highlight_color);
         }
-        style.render_text(
-            pixels, (self.x as i32 - self.width/2 - TEXT_BUFFER) as u32, self.y,
+        painter.draw_text(
+            style, (self.x as i32 - self.width/2 - TEXT_BUFFER, self.y as i32),
             &self.text, BLACK,
             crate::style::TextAlign::Center,
             crate::style::TextAlign::LowerRight
         );
-        style.render_text(
-            pixels, (self.x as i32 + self.width/2 + TEXT_BUFFER) as u32, self.y,
+        painter.draw_text(
+            style, (self.x as i32 + self.width/2 + TEXT_BUFFER, self.y as i32),
             &format!("{}", self.get_value()), BLACK,
             crate::style::TextAlign::Center,
             crate::style::TextAlign::UpperLeft
@@ -95,24 +101,57 @@ impl Element for Slider {
 
     fn mouse_button_down(&mut self, mouse: Mouse) -> super::EventResponse {
         if !self.bbox(mouse) { return EventResponse::NoEvent }
-        self.selected = true;
+        self.selected = Some(mouse.id);
         self.pos = (mouse.x as i32 - (self.x as i32 - self.width/2)) as f32 / self.width as f32;
         self.pos = self.pos.clamp(0., 1.);
         EventResponse::Responded
     }
 
     fn mouse_button_up(&mut self, mouse: Mouse) -> super::EventResponse {
-        self.selected = false;
-        if !self.bbox(mouse) { return EventResponse::NoEvent }
+        let was_selected = self.selected == Some(mouse.id);
+        if was_selected {
+            self.selected = None;
+        }
+        if !was_selected && !self.bbox(mouse) { return EventResponse::NoEvent }
         EventResponse::Responded
     }
 
     fn mouse_move(&mut self, mouse: Mouse) -> super::EventResponse {
-        if mouse.down && self.selected {
+        // Keep dragging even once the pointer leaves the slider
+        if mouse.down && self.selected == Some(mouse.id) {
             self.pos = (mouse.x as i32 - (self.x as i32 - self.width/2)) as f32 / self.width as f32;
             self.pos = self.pos.clamp(0., 1.);
+            return EventResponse::Responded;
         }
         if !self.bbox(mouse) { return EventResponse::NoEvent }
         EventResponse::Responded
     }
-}
\ No newline at end of file
+
+    fn pointer_cancel(&mut self, mouse: Mouse) {
+        if self.selected == Some(mouse.id) {
+            self.selected = None;
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn key_down(&mut self, key: Key) -> EventResponse {
+        let value = self.get_value();
+        match key {
+            Key::ArrowLeft | Key::ArrowDown => self.set_value(value - self.step()),
+            Key::ArrowRight | Key::ArrowUp => self.set_value(value + self.step()),
+            Key::PageDown => self.set_value(value - 10. * self.step()),
+            Key::PageUp => self.set_value(value + 10. * self.step()),
+            Key::Home => self.set_value(self.start),
+            Key::End => self.set_value(self.end),
+            _ => return EventResponse::NoEvent,
+        }
+        EventResponse::Responded
+    }
+}
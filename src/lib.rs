@@ -1,37 +1,50 @@
 #![allow(dead_code)]
 //! Compile with `wasm-pack build --target web``
-//!```
+//!```ignore
 //! use wasm_bindgen::prelude::*;
 //!
 //!mod wikid_wasm;
-//!use wikid_wasm::Applet;
+//!use wikid_wasm::{element::{Button, ElementId}, Applet, Callback, Dim, Style};
 //!
 //!#[wasm_bindgen]
 //!pub struct MyProgram {
 //!    applet: Applet,
+//!    reset: ElementId<Button>,
 //!}
 //!
 //!/// Public methods, exported to JavaScript.
 //!#[wasm_bindgen]
 //!impl MyProgram {
 //!    pub fn new(canvas: String) -> Self {
-//!        let applet = Applet::new(256, 256, canvas);
+//!        let style = Style::default(include_bytes!("font.ttf"));
+//!        let mut applet = Applet::new(256, 256, canvas, style);
+//!        let reset = applet.add(Button::new((Dim::Percent(0.5), Dim::Percent(0.5)), "Reset".to_owned(), 256, 256));
 //!        
 //!        Self {
 //!            applet,
+//!            reset,
 //!        }
 //!    }
 //!
-//!    pub fn render(&self) {
+//!    pub fn render(&mut self) {
 //!        self.applet.render();
 //!    }
 //!
 //!    pub fn tick(&mut self) {
-//!        self.applet.tick();
+//!        for callback in self.applet.tick() {
+//!            match callback {
+//!                Callback::ButtonClicked(id) if id == self.reset => (),
+//!                _ => (),
+//!            }
+//!        }
 //!    }
 //!}
 //! ```
 mod applet;
+mod color;
+mod dash;
+mod painter;
+mod render_target;
 mod style;
 pub mod element;
 mod util;
@@ -40,6 +53,10 @@ mod util;
 mod tests;
 
 pub use applet::{Applet, Callback};
+pub use color::{Color, ColorParseError, ColorSpace};
+pub use dash::{CapStyle, Dasher};
+pub use painter::Painter;
+pub use render_target::{CanvasTarget, ImageTarget, RenderTarget};
 pub use style::{Style, TextAlign};
 pub use util::*;
 
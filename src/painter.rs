@@ -0,0 +1,423 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::{CapStyle, Color, Style, TextAlign};
+
+/// Scanlines per pixel row, which antialias the edges of filled polygons that are not vertical
+const SUBSAMPLES: usize = 4;
+/// Length in pixels of the straight pieces curves are drawn with
+const FLATTEN_STEP: f32 = 2.;
+/// Most straight pieces one curve is drawn with
+const MAX_CURVE_PIECES: usize = 1000;
+
+/// Draws antialiased shapes onto an image. Positions are in pixels, with pixel (i, j) covering from i - 0.5 to i + 0.5
+/// across and from j - 0.5 to j + 0.5 down. Nothing is drawn outside the clip rectangle.
+pub struct Painter<'a> {
+    pixels: &'a mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// Left, top, right and bottom of the pixels which may be drawn on. The right column and bottom row are left out.
+    clip: (i32, i32, i32, i32),
+    /// Composite in linear light rather than on the sRGB encoded channels
+    linear_blending: bool,
+}
+
+/// Fraction of pixel `i` covered by the span from `from` to `to`
+fn overlap(i: i32, from: f32, to: f32) -> f32 {
+    (to.min(i as f32 + 0.5) - from.max(i as f32 - 0.5)).clamp(0., 1.)
+}
+
+/// Fraction of pixel (i, j) covered by a rectangle (left, top, right, bottom)
+fn rect_coverage(i: i32, j: i32, rect: (f32, f32, f32, f32)) -> f32 {
+    overlap(i, rect.0, rect.2) * overlap(j, rect.1, rect.3)
+}
+
+/// Signed distance from (x, y) to the edge of a rounded rectangle, positive inside
+fn rounded_rect_distance(rect: (f32, f32, f32, f32), radius: f32, x: f32, y: f32) -> f32 {
+    let half = ((rect.2 - rect.0) / 2., (rect.3 - rect.1) / 2.);
+    let radius = radius.clamp(0., half.0.min(half.1).max(0.));
+    let q = (
+        (x - (rect.0 + rect.2) / 2.).abs() - (half.0 - radius),
+        (y - (rect.1 + rect.3) / 2.).abs() - (half.1 - radius),
+    );
+    radius - q.0.max(0.).hypot(q.1.max(0.)) - q.0.max(q.1).min(0.)
+}
+
+/// Whether both coordinates of a point are finite
+fn finite(p: (f32, f32)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
+}
+
+/// Coverage of the pixel centered on (x, y) by a line segment from a to b, `line_width` pixels wide, with the given
+/// caps at a and b
+fn segment_coverage(a: (f32, f32), b: (f32, f32), line_width: f32, caps: (CapStyle, CapStyle), x: f32, y: f32) -> f32 {
+    let half_line_width = line_width / 2.;
+    let v = (b.0 - a.0, b.1 - a.1);
+    let length = (v.0*v.0 + v.1*v.1).sqrt();
+    if length == 0. && caps != (CapStyle::Round, CapStyle::Round) {return 0.;}
+    let r = (x - a.0, y - a.1);
+    // Position along the segment, in pixels from a
+    let along = if length > 0. { (r.0*v.0 + r.1*v.1) / length } else { 0. };
+    let across = if length > 0. { (r.0*v.1 - r.1*v.0).abs() / length } else { (r.0*r.0 + r.1*r.1).sqrt() };
+    let coverage = (half_line_width + 0.5 - across).min(1.);
+    if along < 0. || along > length {
+        let (cap, overshoot) = if along < 0. { (caps.0, -along) } else { (caps.1, along - length) };
+        match cap {
+            CapStyle::Butt => coverage.min(0.5 - overshoot),
+            CapStyle::Round => half_line_width + 0.5 - (overshoot*overshoot + across*across).sqrt(),
+        }
+    } else {
+        // Soften butt ends which fall inside a pixel
+        let mut coverage = coverage;
+        if caps.0 == CapStyle::Butt {
+            coverage = coverage.min(along + 0.5);
+        }
+        if caps.1 == CapStyle::Butt {
+            coverage = coverage.min(length - along + 0.5);
+        }
+        coverage
+    }
+}
+
+/// Add `weight` times the covered fraction of each pixel to `coverage`, for a span from `from` to `to` along a row
+fn add_span(coverage: &mut [f32], from: f32, to: f32, weight: f32) {
+    let first = (from + 0.5).floor().max(0.) as usize;
+    let last = ((to + 0.5).ceil().max(0.) as usize).min(coverage.len());
+    for (i, pixel) in coverage.iter_mut().enumerate().take(last).skip(first) {
+        *pixel += overlap(i as i32, from, to) * weight;
+    }
+}
+
+/// Number of straight pieces to draw a curve of about `length` pixels with
+fn pieces(length: f32) -> usize {
+    if length.is_finite() { ((length / FLATTEN_STEP).ceil() as usize).clamp(1, MAX_CURVE_PIECES) } else { 1 }
+}
+
+impl<'a> Painter<'a> {
+    /// Draw onto `pixels`, clipped to the whole image
+    pub fn new(pixels: &'a mut ImageBuffer<Rgba<u8>, Vec<u8>>) -> Self {
+        let clip = (0, 0, pixels.width() as i32, pixels.height() as i32);
+        Self {
+            pixels,
+            clip,
+            linear_blending: false,
+        }
+    }
+
+    /// Composite colors in linear light, which keeps antialiased edges and translucent overlaps from looking too
+    /// dark, rather than on the sRGB encoded channels as most software does. Off by default.
+    pub fn set_linear_blending(&mut self, linear_blending: bool) {
+        self.linear_blending = linear_blending;
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    /// Left, top, right and bottom of the pixels which may be drawn on. The right column and bottom row are left out.
+    pub fn clip(&self) -> (u32, u32, u32, u32) {
+        (self.clip.0 as u32, self.clip.1 as u32, self.clip.2 as u32, self.clip.3 as u32)
+    }
+
+    /// Only draw on the pixels from `left` to `right` across and `top` to `bottom` down, leaving out the right column
+    /// and bottom row. The clip never reaches outside the image.
+    pub fn set_clip(&mut self, clip: (u32, u32, u32, u32)) {
+        let right = clip.2.min(self.width()).max(clip.0.min(self.width()));
+        let bottom = clip.3.min(self.height()).max(clip.1.min(self.height()));
+        self.clip = (clip.0.min(right) as i32, clip.1.min(bottom) as i32, right as i32, bottom as i32);
+    }
+
+    /// Allow drawing on the whole image again
+    pub fn reset_clip(&mut self) {
+        self.clip = (0, 0, self.width() as i32, self.height() as i32);
+    }
+
+    fn in_clip(&self, x: i32, y: i32) -> bool {
+        x >= self.clip.0 && x < self.clip.2 && y >= self.clip.1 && y < self.clip.3
+    }
+
+    /// Columns and rows of the pixels inside the clip which a box (left, top, right, bottom) touches
+    fn pixel_range(&self, bounds: (f32, f32, f32, f32)) -> (std::ops::Range<i32>, std::ops::Range<i32>) {
+        let clamp = |v: f32, low: i32, high: i32| if v.is_nan() { low } else { v.clamp(low as f32, high as f32) as i32 };
+        let columns = clamp((bounds.0 + 0.5).floor(), self.clip.0, self.clip.2)..clamp((bounds.2 + 0.5).ceil(), self.clip.0, self.clip.2);
+        let rows = clamp((bounds.1 + 0.5).floor(), self.clip.1, self.clip.3)..clamp((bounds.3 + 0.5).ceil(), self.clip.1, self.clip.3);
+        (columns, rows)
+    }
+
+    /// Composite a color over a pixel. The color's own opacity is scaled by `coverage`, the fraction of the pixel covered.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Rgba<u8>, coverage: f32) {
+        if !self.in_clip(x, y) || coverage <= 0. {return;}
+        let color = Color::from(color).faded(coverage);
+        let below = Color::from(self.pixels[(x as u32, y as u32)]);
+        let blended = if self.linear_blending { color.over_linear(below) } else { color.over(below) };
+        self.pixels[(x as u32, y as u32)] = blended.into();
+    }
+
+    /// Replace a pixel with a color, without blending
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Rgba<u8>) {
+        if !self.in_clip(x, y) {return;}
+        self.pixels[(x as u32, y as u32)] = color;
+    }
+
+    /// Replace every pixel inside the clip with a color
+    pub fn fill(&mut self, color: Rgba<u8>) {
+        for x in self.clip.0..self.clip.2 {
+            for y in self.clip.1..self.clip.3 {
+                self.pixels[(x as u32, y as u32)] = color;
+            }
+        }
+    }
+
+    /// Copy an image with its upper left pixel at `pos`, without blending
+    pub fn draw_image(&mut self, pos: (i32, i32), image: &ImageBuffer<Rgba<u8>, Vec<u8>>) {
+        for (i, j, pixel) in image.enumerate_pixels() {
+            self.set_pixel(pos.0 + i as i32, pos.1 + j as i32, *pixel);
+        }
+    }
+
+    /// Fill the pixels from `x.0` to `x.1` on row `y`, both ends included, with no antialiasing
+    pub fn draw_h_line(&mut self, y: i32, x: (i32, i32), color: Rgba<u8>) {
+        for x in x.0.min(x.1)..=x.0.max(x.1) {
+            self.blend_pixel(x, y, color, 1.);
+        }
+    }
+
+    /// Fill the pixels from `y.0` to `y.1` in column `x`, both ends included, with no antialiasing
+    pub fn draw_v_line(&mut self, x: i32, y: (i32, i32), color: Rgba<u8>) {
+        for y in y.0.min(y.1)..=y.0.max(y.1) {
+            self.blend_pixel(x, y, color, 1.);
+        }
+    }
+
+    /// Blend a color into the pixels in `bounds` (left, top, right, bottom), each by the coverage `coverage` gives
+    /// for its center
+    fn paint(&mut self, bounds: (f32, f32, f32, f32), color: Rgba<u8>, coverage: impl Fn(f32, f32) -> f32) {
+        let (columns, rows) = self.pixel_range(bounds);
+        for i in columns {
+            for j in rows.clone() {
+                let c = coverage(i as f32, j as f32);
+                if c > 0. {
+                    self.blend_pixel(i, j, color, c);
+                }
+            }
+        }
+    }
+
+    /// Draw a line segment from a to b, `line_width` pixels wide, with the given caps at a and b
+    pub fn draw_line(&mut self, a: (f32, f32), b: (f32, f32), line_width: f32, caps: (CapStyle, CapStyle), color: Rgba<u8>) {
+        if !(finite(a) && finite(b)) {return;}
+        let pad = line_width / 2. + 1.;
+        let bounds = (a.0.min(b.0) - pad, a.1.min(b.1) - pad, a.0.max(b.0) + pad, a.1.max(b.1) + pad);
+        self.paint(bounds, color, |x, y| segment_coverage(a, b, line_width, caps, x, y));
+    }
+
+    /// Draw a solid line through points, with round joins and the given cap at both ends. Each pixel is blended
+    /// once, with the most any segment covers it, so joins do not show in translucent lines.
+    pub fn draw_polyline(&mut self, points: &[(f32, f32)], line_width: f32, cap: CapStyle, color: Rgba<u8>) {
+        let pad = line_width / 2. + 1.;
+        let bounds = points.iter().filter(|p| finite(**p)).fold(
+            (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            |b, p| (b.0.min(p.0 - pad), b.1.min(p.1 - pad), b.2.max(p.0 + pad), b.3.max(p.1 + pad)),
+        );
+        let (columns, rows) = self.pixel_range(bounds);
+        if columns.is_empty() || rows.is_empty() {return;}
+        let width = columns.len();
+        let mut coverage = vec![0f32; width * rows.len()];
+        let last = points.len().saturating_sub(2);
+        for (k, pair) in points.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            if !(finite(a) && finite(b)) {continue;}
+            let caps = (
+                if k == 0 { cap } else { CapStyle::Round },
+                if k == last { cap } else { CapStyle::Round },
+            );
+            let segment = (a.0.min(b.0) - pad, a.1.min(b.1) - pad, a.0.max(b.0) + pad, a.1.max(b.1) + pad);
+            let (segment_columns, segment_rows) = self.pixel_range(segment);
+            for i in segment_columns {
+                for j in segment_rows.clone() {
+                    let pixel = &mut coverage[(j - rows.start) as usize * width + (i - columns.start) as usize];
+                    *pixel = pixel.max(segment_coverage(a, b, line_width, caps, i as f32, j as f32));
+                }
+            }
+        }
+        for (k, c) in coverage.into_iter().enumerate() {
+            if c > 0. {
+                let (i, j) = (columns.start + (k % width) as i32, rows.start + (k / width) as i32);
+                self.blend_pixel(i, j, color, c.min(1.));
+            }
+        }
+    }
+
+    /// Fill a rectangle (left, top, right, bottom)
+    pub fn fill_rect(&mut self, rect: (f32, f32, f32, f32), color: Rgba<u8>) {
+        self.paint(rect, color, |x, y| rect_coverage(x as i32, y as i32, rect));
+    }
+
+    /// Outline a rectangle (left, top, right, bottom) with square corners. The outline is centered on the edges.
+    pub fn draw_rect(&mut self, rect: (f32, f32, f32, f32), line_width: f32, color: Rgba<u8>) {
+        let half = line_width / 2.;
+        let outer = (rect.0 - half, rect.1 - half, rect.2 + half, rect.3 + half);
+        let inner = (rect.0 + half, rect.1 + half, rect.2 - half, rect.3 - half);
+        self.paint(outer, color, |x, y| {
+            rect_coverage(x as i32, y as i32, outer) - rect_coverage(x as i32, y as i32, inner)
+        });
+    }
+
+    /// Fill a rectangle (left, top, right, bottom) with corners rounded to `radius` pixels
+    pub fn fill_rounded_rect(&mut self, rect: (f32, f32, f32, f32), radius: f32, color: Rgba<u8>) {
+        self.fill_distance((rect.0 - 1., rect.1 - 1., rect.2 + 1., rect.3 + 1.), color, |x, y| {
+            rounded_rect_distance(rect, radius, x, y)
+        });
+    }
+
+    /// Outline a rectangle (left, top, right, bottom) with corners rounded to `radius` pixels. The outline is centered on the edges.
+    pub fn draw_rounded_rect(&mut self, rect: (f32, f32, f32, f32), radius: f32, line_width: f32, color: Rgba<u8>) {
+        let pad = line_width / 2. + 1.;
+        self.stroke_distance((rect.0 - pad, rect.1 - pad, rect.2 + pad, rect.3 + pad), line_width, color, |x, y| {
+            rounded_rect_distance(rect, radius, x, y)
+        });
+    }
+
+    /// Outline a circle, with the outline centered on the radius
+    pub fn draw_circle(&mut self, center: (f32, f32), radius: f32, line_width: f32, color: Rgba<u8>) {
+        let pad = radius + line_width / 2. + 1.;
+        self.stroke_distance((center.0 - pad, center.1 - pad, center.0 + pad, center.1 + pad), line_width, color, |x, y| {
+            radius - (x - center.0).hypot(y - center.1)
+        });
+    }
+
+    /// Fill a circle
+    pub fn fill_circle(&mut self, center: (f32, f32), radius: f32, color: Rgba<u8>) {
+        let pad = radius + 1.;
+        self.fill_distance((center.0 - pad, center.1 - pad, center.0 + pad, center.1 + pad), color, |x, y| {
+            radius - (x - center.0).hypot(y - center.1)
+        });
+    }
+
+    /// Fill a shape inside `bounds` (left, top, right, bottom), given by the signed distance in pixels from a point
+    /// to its edge, positive inside
+    pub(crate) fn fill_distance(&mut self, bounds: (f32, f32, f32, f32), color: Rgba<u8>, distance: impl Fn(f32, f32) -> f32) {
+        self.paint(bounds, color, |x, y| distance(x, y) + 0.5);
+    }
+
+    /// Outline a shape inside `bounds` (left, top, right, bottom), given by the signed distance in pixels from a point
+    /// to its edge, positive inside. The outline is centered on the edge.
+    pub(crate) fn stroke_distance(&mut self, bounds: (f32, f32, f32, f32), line_width: f32, color: Rgba<u8>, distance: impl Fn(f32, f32) -> f32) {
+        self.paint(bounds, color, |x, y| line_width / 2. + 0.5 - distance(x, y).abs());
+    }
+
+    /// Draw part of a circle from angle `angles.0` to `angles.1` in radians. Angles are measured from the right,
+    /// and increase clockwise on screen since y runs down.
+    pub fn draw_arc(&mut self, center: (f32, f32), radius: f32, angles: (f32, f32), line_width: f32, cap: CapStyle, color: Rgba<u8>) {
+        let n = pieces(radius.abs() * (angles.1 - angles.0).abs());
+        let points = (0..=n).map(|k| {
+            let angle = angles.0 + (angles.1 - angles.0) * k as f32 / n as f32;
+            (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        }).collect::<Vec<_>>();
+        self.draw_polyline(&points, line_width, cap, color);
+    }
+
+    /// Draw a quadratic Bézier curve from `points[0]` to `points[2]`, pulled towards `points[1]`
+    pub fn draw_quadratic_bezier(&mut self, points: [(f32, f32); 3], line_width: f32, cap: CapStyle, color: Rgba<u8>) {
+        let [p0, p1, p2] = points;
+        self.draw_curve(&points, line_width, cap, color, |t| {
+            let s = 1. - t;
+            (
+                s*s*p0.0 + 2.*s*t*p1.0 + t*t*p2.0,
+                s*s*p0.1 + 2.*s*t*p1.1 + t*t*p2.1,
+            )
+        });
+    }
+
+    /// Draw a cubic Bézier curve from `points[0]` to `points[3]`, leaving towards `points[1]` and arriving from `points[2]`
+    pub fn draw_bezier(&mut self, points: [(f32, f32); 4], line_width: f32, cap: CapStyle, color: Rgba<u8>) {
+        let [p0, p1, p2, p3] = points;
+        self.draw_curve(&points, line_width, cap, color, |t| {
+            let s = 1. - t;
+            (
+                s*s*s*p0.0 + 3.*s*s*t*p1.0 + 3.*s*t*t*p2.0 + t*t*t*p3.0,
+                s*s*s*p0.1 + 3.*s*s*t*p1.1 + 3.*s*t*t*p2.1 + t*t*t*p3.1,
+            )
+        });
+    }
+
+    /// Draw a curve given by its position at t from 0 to 1, in pieces short enough for the length of its control polygon
+    fn draw_curve(&mut self, control: &[(f32, f32)], line_width: f32, cap: CapStyle, color: Rgba<u8>, at: impl Fn(f32) -> (f32, f32)) {
+        let length = control.windows(2).map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1)).sum::<f32>();
+        let n = pieces(length);
+        let points = (0..=n).map(|k| at(k as f32 / n as f32)).collect::<Vec<_>>();
+        self.draw_polyline(&points, line_width, cap, color);
+    }
+
+    /// Fill a polygon. Points are filled if the polygon winds around them, so self-intersections are filled too.
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)], color: Rgba<u8>) {
+        self.fill_polygons(&[points.to_vec()], color);
+    }
+
+    /// Fill several polygons as one shape. Points are filled if the polygons wind around them, and each pixel is
+    /// blended once, so overlaps and self-intersections do not show in translucent fills.
+    pub fn fill_polygons(&mut self, polygons: &[Vec<(f32, f32)>], color: Rgba<u8>) {
+        let (left, right) = (self.clip.0 as f32 - 0.5, self.clip.2 as f32 - 0.5);
+        let (top, bottom) = (self.clip.1 as f32 - 0.5, self.clip.3 as f32 - 0.5);
+        if right <= left || bottom <= top {return;}
+
+        // Each edge from its upper end to its lower end, with +1 if it runs downwards and -1 if upwards.
+        // Horizontal edges are left out, since no scanline crosses them.
+        let mut edges = Vec::new();
+        for polygon in polygons {
+            if polygon.iter().any(|p| !(p.0.is_finite() && p.1.is_finite())) {continue;}
+            for (k, a) in polygon.iter().enumerate() {
+                let b = polygon[(k + 1) % polygon.len()];
+                if a.1 == b.1 {continue;}
+                edges.push(if a.1 < b.1 { (*a, b, 1) } else { (b, *a, -1) });
+            }
+        }
+        if edges.is_empty() {return;}
+        let y_min = edges.iter().map(|e| e.0.1).fold(f32::INFINITY, f32::min).max(top);
+        let y_max = edges.iter().map(|e| e.1.1).fold(f32::NEG_INFINITY, f32::max).min(bottom);
+        if y_max <= y_min {return;}
+
+        let mut coverage = vec![0.; self.clip.2 as usize];
+        let mut crossings = Vec::new();
+        for j in (y_min + 0.5).floor() as i32..((y_max + 0.5).ceil() as i32).min(self.clip.3) {
+            coverage.fill(0.);
+            for s in 0..SUBSAMPLES {
+                let y = j as f32 - 0.5 + (s as f32 + 0.5) / SUBSAMPLES as f32;
+                crossings.clear();
+                for (upper, lower, winding) in &edges {
+                    // Half open, so that a scanline through a vertex crosses only one of the edges meeting there
+                    if y < upper.1 || y >= lower.1 {continue;}
+                    let x = upper.0 + (y - upper.1) / (lower.1 - upper.1) * (lower.0 - upper.0);
+                    crossings.push((x, *winding));
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    if winding == 0 {continue;}
+                    let (from, to) = (pair[0].0.max(left), pair[1].0.min(right));
+                    if from < to {
+                        add_span(&mut coverage, from, to, 1. / SUBSAMPLES as f32);
+                    }
+                }
+            }
+            for i in self.clip.0..self.clip.2 {
+                if coverage[i as usize] > 0. {
+                    self.blend_pixel(i, j, color, coverage[i as usize]);
+                }
+            }
+        }
+    }
+
+    /// Write text as `Style::render_text` does, inside the clip
+    pub fn draw_text(&mut self, style: &Style, pos: (i32, i32), text: &str, color: Rgba<u8>, va: TextAlign, ha: TextAlign) {
+        style.text_pixels(pos.0, pos.1, text, va, ha, |x, y, v| self.blend_pixel(x, y, color, v));
+    }
+
+    /// Write text as `Style::render_rotated_text` does, inside the clip
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rotated_text(&mut self, style: &Style, pos: (i32, i32), text: &str, color: Rgba<u8>, va: TextAlign, ha: TextAlign, rotation: f32) {
+        style.rotated_text_pixels(pos.0, pos.1, text, va, ha, rotation, |x, y, v| self.blend_pixel(x, y, color, v));
+    }
+}
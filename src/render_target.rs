@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use image::{ImageBuffer, Rgba};
+use wasm_bindgen::{prelude::*, Clamped};
+use web_sys::ImageData;
+
+/// Somewhere to put the frames produced by `Applet::render`
+pub trait RenderTarget {
+    /// Present a finished RGBA frame
+    fn present(&mut self, frame: &ImageBuffer<Rgba<u8>, Vec<u8>>);
+}
+
+/// Render target which draws to an HTML canvas in the browser
+pub struct CanvasTarget {
+    name: String,
+}
+
+impl CanvasTarget {
+    /// Look up the canvas with id `name` and resize it to the applet
+    pub fn new(name: String, width: u32, height: u32) -> Self {
+        let window = web_sys::window().expect("No global `window` exists");
+        let document = window.document().expect("Should have a document on window");
+        let canvas = document.get_element_by_id(&name).expect("Could not find the canvas");
+        canvas.set_attribute("width", &width.to_string()).expect("The provided HTML object was not a canvas");
+        canvas.set_attribute("height", &height.to_string()).expect("The provided HTML object was not a canvas");
+        Self {
+            name
+        }
+    }
+}
+
+impl RenderTarget for CanvasTarget {
+    fn present(&mut self, frame: &ImageBuffer<Rgba<u8>, Vec<u8>>) {
+        let window = web_sys::window().unwrap();
+        let document = window.document().expect("Could not get document");
+        let canvas = document
+            .get_element_by_id(&self.name)
+            .unwrap()
+            .dyn_into::<web_sys::HtmlCanvasElement>().unwrap();
+        let context = canvas
+            .get_context("2d").unwrap()
+            .unwrap()
+            .dyn_into::<web_sys::CanvasRenderingContext2d>().unwrap();
+        let clamped_buf: Clamped<&[u8]> = Clamped(frame.as_raw());
+        let image_data_temp =
+            ImageData::new_with_u8_clamped_array_and_sh(clamped_buf, frame.width(), frame.height()).unwrap();
+        context.put_image_data(&image_data_temp, 0.0, 0.0).unwrap();
+    }
+}
+
+/// Render target which keeps frames in memory, for running applets off the browser.
+/// If a path is given, every frame is also written there as a PNG.
+pub struct ImageTarget {
+    frame: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    frame_count: usize,
+    path: Option<PathBuf>,
+}
+
+impl ImageTarget {
+    pub fn new() -> Self {
+        Self {
+            frame: ImageBuffer::new(0, 0),
+            frame_count: 0,
+            path: None,
+        }
+    }
+
+    /// Also save each presented frame to `path`
+    pub fn to_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::new()
+        }
+    }
+
+    /// The most recently presented frame
+    pub fn frame(&self) -> &ImageBuffer<Rgba<u8>, Vec<u8>> {
+        &self.frame
+    }
+
+    /// Number of frames presented so far
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+}
+
+impl Default for ImageTarget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderTarget for ImageTarget {
+    fn present(&mut self, frame: &ImageBuffer<Rgba<u8>, Vec<u8>>) {
+        self.frame.clone_from(frame);
+        self.frame_count += 1;
+        if let Some(path) = &self.path {
+            frame.save(path).expect("Could not save the frame");
+        }
+    }
+}
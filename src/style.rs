@@ -1,24 +1,30 @@
 use image::Rgba;
 use rusttype::{point, Font, Scale};
 
-use super::{blend_color, hex_to_rgba};
+use super::{blend_color, hex_to_rgba, parse_color, ColorParseError};
 
 const FONT_SCALE: f32 = 1.5;
 pub const BLACK: image::Rgba<u8> = Rgba([0, 0, 0, 255]);
 pub const WHITE: image::Rgba<u8> = Rgba([255, 255, 255, 255]);
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TextAlign {
     UpperLeft,
     LowerRight,
     Center,
 }
 
+#[derive(Clone)]
 pub struct Style {
     pub font: Font<'static>,
     pub font_size: u32,
+    /// Color of buttons, sliders and the plot crosshair and tooltip. Plot series do not use it: they take
+    /// `SeriesStyle::color`, or else the colors of `COLOR_CYCLE` in turn.
     pub highlight_color: Rgba<u8>,
     pub point_radius: f32,
     pub line_width: f32, 
+    /// Composite colors in linear light. See `Painter::set_linear_blending`.
+    pub linear_blending: bool,
 }
 
 impl Style {
@@ -31,14 +37,19 @@ impl Style {
             highlight_color: hex_to_rgba("#888888"),
             point_radius: 4.,
             line_width: 3.,
+            linear_blending: false,
         }
     }
 
-    pub fn set_color(&mut self, color: &str) {
-        self.highlight_color = hex_to_rgba(color);
+    /// Set the highlight color from a CSS color. An invalid color leaves it unchanged. This does not color plot
+    /// series; see `highlight_color`.
+    pub fn set_color(&mut self, color: &str) -> Result<(), ColorParseError> {
+        self.highlight_color = parse_color(color)?;
+        Ok(())
     }
 
-    pub fn render_text(&self, pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, start_x: u32, start_y: u32, text: &str, color: image::Rgba<u8>, va: TextAlign, ha: TextAlign) {
+    /// Width and height in pixels of the box `render_text` aligns `text` in
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
         let scale = Scale {
             x: self.font_size as f32 * FONT_SCALE,
             y: self.font_size as f32 * FONT_SCALE,
@@ -46,47 +57,28 @@ impl Style {
         let scaled_font_size = (self.font_size as f32 * FONT_SCALE) as u32;
         let v_metrics = self.font.v_metrics(scale);
         let offset = point(0.0, v_metrics.ascent);
-        let glyphs: Vec<_> = self.font.layout(text, scale, offset).collect();
-
-        // Find the most visually pleasing width to display
-        let width = glyphs
-            .iter()
-            .rev()
-            .map(|g| g.position().x as f32 + g.unpositioned().h_metrics().advance_width)
-            .next()
+        let width = self.font.layout(text, scale, offset)
+            .last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
             .unwrap_or(0.0)
-            .ceil() as usize;
-
-        let start_x = match ha {
-            TextAlign::UpperLeft => start_x as i32,
-            TextAlign::LowerRight => start_x as i32 - width as i32,
-            TextAlign::Center => start_x as i32 - width as i32 / 2,
-        };
-
-        let start_y = match va {
-            TextAlign::UpperLeft => start_y as i32,
-            TextAlign::LowerRight => start_y as i32 - scaled_font_size as i32,
-            TextAlign::Center => start_y as i32 - (scaled_font_size as i32) / 2,
-        };
+            .ceil() as u32;
+        (width, scaled_font_size)
+    }
 
-        for g in glyphs {
-            if let Some(bb) = g.pixel_bounding_box() {
-                g.draw(|x, y, v| {
-                    let x = x as i32 + bb.min.x;
-                    let y = y as i32 + bb.min.y;
-                    if x >= 0 && x < width as i32 && y >= 0 && y < scaled_font_size as i32 {
-                        let x = x + start_x;
-                        let y = y + start_y;
-                        if x < 0 || y < 0 || x >= pixels.width() as i32 || y >= pixels.height() as i32 {return;}
-                        let empty = pixels[(x as u32, y as u32)];
-                        pixels[(x as u32, y as u32)] = blend_color(color, empty, v);
-                    }
-                });
-            }
-        }
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_text(&self, pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, start_x: u32, start_y: u32, text: &str, color: image::Rgba<u8>, va: TextAlign, ha: TextAlign) {
+        self.text_pixels(start_x as i32, start_y as i32, text, va, ha, |x, y, v| blend_into(pixels, x, y, color, v));
     }
 
+    /// Like `render_text`, but turned `rotation` degrees counterclockwise about the start, which the alignments
+    /// place on the text as they do for upright text
+    #[allow(clippy::too_many_arguments)]
     pub fn render_rotated_text(&self, pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, start_x: u32, start_y: u32, text: &str, color: image::Rgba<u8>, va: TextAlign, ha: TextAlign, rotation: f32) {
+        self.rotated_text_pixels(start_x as i32, start_y as i32, text, va, ha, rotation, |x, y, v| blend_into(pixels, x, y, color, v));
+    }
+
+    /// Pass each pixel `render_text` would cover to `put`, with the fraction of it covered
+    pub(crate) fn text_pixels(&self, start_x: i32, start_y: i32, text: &str, va: TextAlign, ha: TextAlign, mut put: impl FnMut(i32, i32, f32)) {
         let scale = Scale {
             x: self.font_size as f32 * FONT_SCALE,
             y: self.font_size as f32 * FONT_SCALE,
@@ -100,54 +92,99 @@ impl Style {
         let width = glyphs
             .iter()
             .rev()
-            .map(|g| g.position().x as f32 + g.unpositioned().h_metrics().advance_width)
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
             .next()
             .unwrap_or(0.0)
             .ceil() as usize;
 
-        let offset_local_x = match ha {
-            TextAlign::UpperLeft => 0.,
-            TextAlign::LowerRight => -(width as f32),
-            TextAlign::Center => -(width as f32) / 2.,
+        let start_x = match ha {
+            TextAlign::UpperLeft => start_x,
+            TextAlign::LowerRight => start_x - width as i32,
+            TextAlign::Center => start_x - width as i32 / 2,
         };
 
-        let offset_local_y = match va {
-            TextAlign::UpperLeft => 0.,
-            TextAlign::LowerRight => -(scaled_font_size as f32),
-            TextAlign::Center => -(scaled_font_size as f32) / 2.,
+        let start_y = match va {
+            TextAlign::UpperLeft => start_y,
+            TextAlign::LowerRight => start_y - scaled_font_size as i32,
+            TextAlign::Center => start_y - (scaled_font_size as i32) / 2,
         };
 
-        let rot_rad = rotation * std::f32::consts::PI / 180.;
-        let start_x = (start_x as f32 + offset_local_x * rot_rad.cos() + offset_local_y * rot_rad.sin()) as i32;
-        let start_y = (start_y as f32 + offset_local_y * rot_rad.cos() - offset_local_x * rot_rad.sin()) as i32;
-
         for g in glyphs {
             if let Some(bb) = g.pixel_bounding_box() {
                 g.draw(|x, y, v| {
                     let x = x as i32 + bb.min.x;
                     let y = y as i32 + bb.min.y;
                     if x >= 0 && x < width as i32 && y >= 0 && y < scaled_font_size as i32 {
-                        // Rotate
-                        let (x, y) = if rotation == 0. {
-                            (x, y)
-                        } else if rotation == 90. {
-                            (y, -x)
-                        } else if rotation == 180. {
-                            (-x, -y)
-                        } else if rotation == 270. {
-                            (-y, x)
-                        } else {
-                            unimplemented!()
-                        };
-
-                        let x = x + start_x;
-                        let y = y + start_y;
-                        if x < 0 || y < 0 || x >= pixels.width() as i32 || y >= pixels.height() as i32 {return;}
-                        let empty = pixels[(x as u32, y as u32)];
-                        pixels[(x as u32, y as u32)] = blend_color(color, empty, v);
+                        put(x + start_x, y + start_y, v);
                     }
                 });
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Pass each pixel `render_rotated_text` would cover to `put`, with the fraction of it covered
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn rotated_text_pixels(&self, start_x: i32, start_y: i32, text: &str, va: TextAlign, ha: TextAlign, rotation: f32, mut put: impl FnMut(i32, i32, f32)) {
+        if rotation.rem_euclid(360.) == 0. {
+            return self.text_pixels(start_x, start_y, text, va, ha, put);
+        }
+
+        // Coverage of the upright text, with its upper left corner at the origin
+        let (width, height) = self.text_size(text);
+        let (width, height) = (width as i32, height as i32);
+        let mut coverage = vec![0f32; (width * height) as usize];
+        self.text_pixels(0, 0, text, TextAlign::UpperLeft, TextAlign::UpperLeft, |x, y, v| {
+            let c = &mut coverage[(y * width + x) as usize];
+            *c = (*c + v).min(1.);
+        });
+        let sample = |x: i32, y: i32| {
+            if x < 0 || y < 0 || x >= width || y >= height { 0. } else { coverage[(y * width + x) as usize] }
+        };
+
+        // The point of the upright text which lands on the start, as `render_text` aligns it
+        let align = |align: TextAlign, size: i32| match align {
+            TextAlign::UpperLeft => 0.,
+            TextAlign::LowerRight => size as f32,
+            TextAlign::Center => (size / 2) as f32,
+        };
+        let anchor = (align(ha, width), align(va, height));
+
+        // Counterclockwise on screen. Quarter turns are made exact, so they move whole pixels onto whole pixels.
+        let exact = |v: f32| if (v - v.round()).abs() < 1e-6 { v.round() } else { v };
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let (sin, cos) = (exact(sin), exact(cos));
+        let (start_x, start_y) = (start_x as f32, start_y as f32);
+        let corners = [(0., 0.), (width as f32, 0.), (0., height as f32), (width as f32, height as f32)].map(|(u, v)| {
+            let (u, v) = (u - anchor.0, v - anchor.1);
+            (start_x + u * cos + v * sin, start_y - u * sin + v * cos)
+        });
+        let left = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min).floor() as i32;
+        let right = corners.iter().map(|c| c.0).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+        let top = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min).floor() as i32;
+        let bottom = corners.iter().map(|c| c.1).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+
+        // Map the center of each pixel back onto the upright text, and sample it between the four nearest pixels
+        for y in top..bottom {
+            for x in left..right {
+                let (dx, dy) = (x as f32 + 0.5 - start_x, y as f32 + 0.5 - start_y);
+                let u = anchor.0 + dx * cos - dy * sin - 0.5;
+                let v = anchor.1 + dx * sin + dy * cos - 0.5;
+                let (u0, v0) = (u.floor(), v.floor());
+                let (tu, tv) = (u - u0, v - v0);
+                let (u0, v0) = (u0 as i32, v0 as i32);
+                let c = (sample(u0, v0) * (1. - tu) + sample(u0 + 1, v0) * tu) * (1. - tv)
+                    + (sample(u0, v0 + 1) * (1. - tu) + sample(u0 + 1, v0 + 1) * tu) * tv;
+                if c > 0. {
+                    put(x, y, c);
+                }
+            }
+        }
+    }
+}
+
+/// Blend a color into a pixel of an image, if the pixel is in it
+fn blend_into(pixels: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, x: i32, y: i32, color: image::Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= pixels.width() as i32 || y >= pixels.height() as i32 {return;}
+    let empty = pixels[(x as u32, y as u32)];
+    pixels[(x as u32, y as u32)] = blend_color(color, empty, coverage);
+}
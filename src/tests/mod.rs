@@ -0,0 +1,1084 @@
+mod snapshot;
+
+use std::io::Write;
+use rusttype::{point, Font, Scale};
+
+use crate::{element::{histogram, AutoLimits, AxesStyle, Bins, Button, CanvasElement, Shape, ShapeStyle, Transform, Colormap, ContourStyle, ErrorBarOptions, Errors, HistOptions, HistType, GridData, ImageStyle, Interpolation, DynamicPlot, Element, EventResponse, Gesture, GridStyle, LegendLoc, LineStyle, Marker, Mouse, PlotCommand, AxisScale, SeriesStyle, Slider, SliderType, Spines, TickDirection, MOUSE_POINTER}, style::{TextAlign, BLACK, WHITE}, Applet, Callback, CapStyle, Color, ColorParseError, ColorSpace, Dasher, Dim, Painter, Style, fnanmax, fnanmin, parse_color};
+use snapshot::{assert_snapshot, diff_images, render_element, DEFAULT_TOLERANCE};
+
+const FONT_DATA: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
+
+#[test]
+fn font_test() {
+    let font_data = FONT_DATA;
+    let font = Font::try_from_bytes(font_data as &[u8]).expect("error constructing a Font from bytes");
+
+    // Desired font pixel height
+    let height: f32 = 12.0; // to get 80 chars across (fits most terminals); adjust as desired
+    let pixel_height = height.ceil() as usize;
+
+    // 2x scale in x direction to counter the aspect ratio of monospace characters.
+    let scale = Scale {
+        x: height * 2.0,
+        y: height,
+    };
+
+    // The origin of a line of text is at the baseline (roughly where
+    // non-descending letters sit). We don't want to clip the text, so we shift
+    // it down with an offset when laying it out. v_metrics.ascent is the
+    // distance between the baseline and the highest edge of any glyph in
+    // the font. That's enough to guarantee that there's no clipping.
+    let v_metrics = font.v_metrics(scale);
+    let offset = point(0.0, v_metrics.ascent);
+
+    // Glyphs to draw for "RustType". Feel free to try other strings.
+    let glyphs: Vec<_> = font.layout("H", scale, offset).collect();
+
+    // Find the most visually pleasing width to display
+    let width = glyphs
+        .iter()
+        .rev()
+        .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+        .next()
+        .unwrap_or(0.0)
+        .ceil() as usize;
+
+    println!("width: {}, height: {}", width, pixel_height);
+
+    // Rasterise directly into ASCII art.
+    let mut pixel_data = vec![b'@'; width * pixel_height];
+    let mapping = b"@%#x+=:-. "; // The approximation of greyscale
+    let mapping_scale = (mapping.len() - 1) as f32;
+    for g in glyphs {
+        if let Some(bb) = g.pixel_bounding_box() {
+            g.draw(|x, y, v| {
+                // v should be in the range 0.0 to 1.0
+                let i = (v * mapping_scale + 0.5) as usize;
+                // so something's wrong if you get $ in the output.
+                let c = mapping.get(i).cloned().unwrap_or(b'$');
+                let x = x as i32 + bb.min.x;
+                let y = y as i32 + bb.min.y;
+                // There's still a possibility that the glyph clips the boundaries of the bitmap
+                if x >= 0 && x < width as i32 && y >= 0 && y < pixel_height as i32 {
+                    let x = x as usize;
+                    let y = y as usize;
+                    pixel_data[x + y * width] = c;
+                }
+            })
+        }
+    }
+
+    // Print it out
+    let stdout = ::std::io::stdout();
+    let mut handle = stdout.lock();
+    for j in 0..pixel_height {
+        handle
+            .write_all(&pixel_data[j * width..(j + 1) * width])
+            .unwrap();
+        handle.write_all(b"\n").unwrap();
+    }
+}
+
+#[test]
+fn headless_render() {
+    let mut applet = Applet::headless(256, 128, Style::default(FONT_DATA));
+    let button = applet.add(Button::new((Dim::Pixel(128), Dim::Pixel(64)), "Go".to_owned(), 256, 128));
+
+    applet.render();
+    assert_eq!(applet.frame().dimensions(), (256, 128));
+    let idle = applet.frame()[(100, 60)];
+
+    // Hovering fills the button with a light highlight
+    applet.mouse_move(100, 60);
+    applet.render();
+    let hover = applet.frame()[(100, 60)];
+    assert_ne!(idle, hover);
+
+    // Clicking places a callback which refers to the button
+    applet.mouse_button_down(100, 60);
+    applet.mouse_button_up(100, 60);
+    let callbacks = applet.tick().collect::<Vec<_>>();
+    assert_eq!(callbacks, vec![Callback::ButtonClicked(button)]);
+}
+
+#[test]
+fn element_registry() {
+    let mut applet = Applet::headless(256, 128, Style::default(FONT_DATA));
+    let slider = applet.add(Slider::new(
+        Dim::Pixel(128), Dim::Pixel(64), "k".to_owned(), SliderType::Int, [0., 10., 5.], 256, 128
+    ));
+    let button = applet.add(Button::new((Dim::Pixel(128), Dim::Pixel(100)), "Go".to_owned(), 256, 128));
+    assert_eq!(applet.element_count(), 2);
+    assert_ne!(slider.index(), button.index());
+
+    // Dragging the slider past its end clamps it, and the handle gives typed access to the value
+    applet.mouse_button_down(128, 64);
+    applet.mouse_move(250, 64);
+    applet.mouse_button_up(250, 64);
+    assert_eq!(applet.get(slider).get_value(), 10.);
+    assert_eq!(applet.tick().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "does not belong to this registry")]
+fn element_registry_foreign_id() {
+    // A handle from one applet matches nothing in another, even at the same index and type
+    let mut first = Applet::headless(256, 128, Style::default(FONT_DATA));
+    let mut second = Applet::headless(256, 128, Style::default(FONT_DATA));
+    let button = first.add(Button::new((Dim::Pixel(128), Dim::Pixel(64)), "One".to_owned(), 256, 128));
+    let other = second.add(Button::new((Dim::Pixel(128), Dim::Pixel(64)), "Two".to_owned(), 256, 128));
+    assert_eq!(button.index(), other.index());
+    assert_ne!(button, other);
+    second.get(button);
+}
+
+#[test]
+fn keyboard_focus() {
+    let mut applet = Applet::headless(256, 128, Style::default(FONT_DATA));
+    let slider = applet.add(Slider::new(
+        Dim::Pixel(128), Dim::Pixel(30), "k".to_owned(), SliderType::Float(1), [0., 1., 0.5], 256, 128
+    ));
+    let button = applet.add(Button::new((Dim::Pixel(128), Dim::Pixel(80)), "Go".to_owned(), 256, 128));
+
+    // Nothing is focused, so keys are not used
+    assert!(!applet.key_down("ArrowRight", false));
+
+    // Tab focuses the slider, and arrows step it
+    assert!(applet.key_down("Tab", false));
+    assert_eq!(applet.focused(), Some(slider.index()));
+    assert!(applet.key_down("ArrowRight", false));
+    assert!(applet.key_down("ArrowRight", false));
+    assert!((applet.get(slider).get_value() - 0.7).abs() < 1e-6);
+
+    // Tab again moves to the button, which activates with Enter
+    assert!(applet.key_down("Tab", false));
+    assert!(applet.key_down("Enter", false));
+    assert_eq!(applet.tick().collect::<Vec<_>>(), vec![Callback::ButtonClicked(button)]);
+    // Space clicks once on release, however long it repeats while held
+    assert!(applet.key_down(" ", false));
+    assert!(applet.key_down(" ", false));
+    assert_eq!(applet.tick().count(), 0);
+    assert!(applet.key_up(" "));
+    assert_eq!(applet.tick().collect::<Vec<_>>(), vec![Callback::ButtonClicked(button)]);
+
+    // Tabbing past the last element releases the focus, and shift-tab comes back to it
+    assert!(!applet.key_down("Tab", false));
+    assert_eq!(applet.focused(), None);
+    assert!(applet.key_down("Tab", true));
+    assert_eq!(applet.focused(), Some(button.index()));
+
+    // Clicking the slider focuses it
+    applet.mouse_button_down(128, 30);
+    applet.mouse_button_up(128, 30);
+    assert_eq!(applet.focused(), Some(slider.index()));
+}
+
+/// Element which records the gestures it is sent
+struct GestureRecorder {
+    gestures: Vec<Gesture>,
+    cancelled: usize,
+}
+
+impl Element for GestureRecorder {
+    fn draw(&self, _painter: &mut Painter, _style: &Style) {}
+
+    fn bbox(&self, mouse: Mouse) -> bool {
+        mouse.y >= 100
+    }
+
+    fn pointer_cancel(&mut self, _mouse: Mouse) {
+        self.cancelled += 1;
+    }
+
+    fn wants_gestures(&self) -> bool {
+        true
+    }
+
+    fn gesture(&mut self, gesture: Gesture) -> EventResponse {
+        self.gestures.push(gesture);
+        EventResponse::Responded
+    }
+}
+
+#[test]
+fn multi_pointer() {
+    let mut applet = Applet::headless(256, 200, Style::default(FONT_DATA));
+    let slider_a = applet.add(Slider::new(
+        Dim::Pixel(128), Dim::Pixel(20), "a".to_owned(), SliderType::Int, [0., 10., 5.], 256, 200
+    ));
+    let slider_b = applet.add(Slider::new(
+        Dim::Pixel(128), Dim::Pixel(60), "b".to_owned(), SliderType::Int, [0., 10., 5.], 256, 200
+    ));
+    let recorder = applet.add(GestureRecorder { gestures: Vec::new(), cancelled: 0 });
+
+    // Two fingers drag two sliders at once, and each keeps its slider when it leaves the slider's box
+    applet.pointer_down(11, 128, 20);
+    applet.pointer_down(12, 128, 60);
+    applet.pointer_move(11, 0, 90);
+    applet.pointer_move(12, 255, 150);
+    assert_eq!(applet.get(slider_a).get_value(), 0.);
+    assert_eq!(applet.get(slider_b).get_value(), 10.);
+    applet.pointer_up(11, 0, 90);
+    applet.pointer_cancel(12);
+
+    // Two fingers on a gesture element pinch and pan
+    applet.pointer_down(13, 100, 150);
+    applet.pointer_down(14, 120, 150);
+    assert_eq!(applet.get(recorder).cancelled, 2);
+    applet.pointer_move(14, 140, 150);
+    applet.pointer_up(13, 100, 150);
+    applet.pointer_up(14, 140, 150);
+    assert_eq!(applet.get(recorder).gestures, vec![
+        Gesture::Pan { dx: 10., dy: 0. },
+        Gesture::Pinch { center: (120., 150.), scale: 2. },
+    ]);
+}
+
+#[test]
+fn pointer_capture() {
+    let mut applet = Applet::headless(256, 128, Style::default(FONT_DATA));
+    applet.add(Slider::new(
+        Dim::Pixel(128), Dim::Pixel(30), "k".to_owned(), SliderType::Int, [0., 10., 5.], 256, 128
+    ));
+    let button = applet.add(Button::new((Dim::Pixel(128), Dim::Pixel(90)), "Go".to_owned(), 256, 128));
+
+    // A second finger takes the slider over, so the slider ignores the first finger's release. The release still
+    // goes nowhere else, so the button under it is not clicked.
+    applet.pointer_down(1, 128, 30);
+    applet.pointer_down(2, 128, 30);
+    applet.pointer_up(2, 128, 30);
+    applet.pointer_up(1, 128, 90);
+    assert_eq!(applet.tick().count(), 0);
+
+    // Pressing and releasing on the button clicks it
+    applet.pointer_down(3, 128, 90);
+    applet.pointer_up(3, 128, 90);
+    assert_eq!(applet.tick().collect::<Vec<_>>(), vec![Callback::ButtonClicked(button)]);
+}
+
+#[test]
+fn plot_pan_zoom() {
+    let mut applet = Applet::headless(300, 220, Style::default(FONT_DATA));
+    let plot = applet.add(DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(300), Dim::Pixel(220)), 300, 220));
+    let replot = |applet: &mut Applet| {
+        let commands = vec![
+            PlotCommand::SetXLim { low: 0., high: 10. },
+            PlotCommand::SetYLim { low: 0., high: 10. },
+            PlotCommand::Line { xs: vec![0., 10.], ys: vec![0., 10.], ls: LineStyle::Solid, style: SeriesStyle::default() },
+        ];
+        let style = applet.style.clone();
+        applet.get_mut(plot).plot(commands, &style);
+    };
+    replot(&mut applet);
+    let (x_lim, y_lim) = (applet.get(plot).x_lim(), applet.get(plot).y_lim());
+    assert_eq!((x_lim, y_lim), ((0., 10.), (0., 10.)));
+
+    // Scrolling up zooms in about the mouse
+    let (left, top, width, height) = applet.get(plot).axes_rect();
+    let center = (left + width / 2, top + height / 2);
+    assert!(applet.wheel(center.0, center.1, -200.));
+    let zoomed = applet.get(plot).x_lim();
+    assert!(zoomed.1 - zoomed.0 < 10.);
+    assert!(zoomed.0 > 0. && zoomed.1 < 10.);
+
+    // Both ends of the line are now outside the limits, and it is cut at the edges of the axes rather than dropped
+    applet.render();
+    assert!(applet.frame().pixels().any(|p| *p == image::Rgba([31, 119, 180, 255])));
+    assert_snapshot("plot_pan_zoom_line", applet.frame(), DEFAULT_TOLERANCE);
+
+    // Dragging to the right moves the view to the left
+    applet.mouse_button_down(center.0, center.1);
+    applet.mouse_move(center.0 + 20, center.1);
+    applet.mouse_button_up(center.0 + 20, center.1);
+    let panned = applet.get(plot).x_lim();
+    assert!(panned.0 < zoomed.0);
+    assert!(((panned.1 - panned.0) - (zoomed.1 - zoomed.0)).abs() < 1e-4);
+
+    // The view survives replotting, until double clicking restores the limits set by the author
+    replot(&mut applet);
+    assert_eq!(applet.get(plot).x_lim(), panned);
+    applet.double_click(center.0, center.1);
+    assert_eq!(applet.get(plot).x_lim(), (0., 10.));
+}
+
+#[test]
+fn snapshot_diff() {
+    let a = image::ImageBuffer::from_pixel(4, 4, WHITE);
+    let mut b = a.clone();
+    b[(1, 2)] = image::Rgba([254, 255, 255, 255]);
+    b[(3, 3)] = image::Rgba([0, 0, 0, 255]);
+    let (count, diff) = diff_images(&a, &b, 1);
+    assert_eq!(count, 1);
+    assert_eq!(diff[(3, 3)], image::Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+#[should_panic(expected = "is missing")]
+fn snapshot_missing() {
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        panic!("Snapshot not_a_snapshot is missing");
+    }
+    assert_snapshot("not_a_snapshot", &image::ImageBuffer::from_pixel(4, 4, WHITE), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn button_snapshot() {
+    let style = Style::default(FONT_DATA);
+    let mut button = Button::new((Dim::Pixel(80), Dim::Pixel(32)), "Reset".to_owned(), 160, 64);
+    assert_snapshot("button", &render_element(&button, 160, 64, &style), DEFAULT_TOLERANCE);
+
+    button.mouse_move(Mouse { x: 80, y: 32, down: false, id: MOUSE_POINTER });
+    assert_snapshot("button_hover", &render_element(&button, 160, 64, &style), DEFAULT_TOLERANCE);
+
+    button.set_focus(true);
+    assert_snapshot("button_focus", &render_element(&button, 160, 64, &style), DEFAULT_TOLERANCE);
+
+    // Not added to an applet, so a click has no callback to place and is not swallowed
+    let click = Mouse { x: 80, y: 32, down: false, id: MOUSE_POINTER };
+    assert!(matches!(button.mouse_button_up(click), EventResponse::NoEvent));
+}
+
+#[test]
+fn slider_snapshot() {
+    let style = Style::default(FONT_DATA);
+    let mut slider = Slider::new(
+        Dim::Pixel(128), Dim::Pixel(24), "k".to_owned(), SliderType::Float(1), [0., 2., 0.5], 256, 48
+    );
+    assert_snapshot("slider", &render_element(&slider, 256, 48, &style), DEFAULT_TOLERANCE);
+
+    slider.set_focus(true);
+    assert_snapshot("slider_focus", &render_element(&slider, 256, 48, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn painter_primitives() {
+    let style = Style::default(FONT_DATA);
+    let blue = image::Rgba([31, 119, 180, 255]);
+    let orange = image::Rgba([255, 127, 14, 160]);
+    let mut pixels = image::ImageBuffer::from_pixel(240, 160, WHITE);
+    let mut painter = Painter::new(&mut pixels);
+    painter.draw_line((10., 10.), (70., 40.), 3., (CapStyle::Round, CapStyle::Butt), blue);
+    painter.draw_polyline(&[(10., 60.), (30., 45.), (50., 60.), (70., 45.)], 2., CapStyle::Round, blue);
+    painter.fill_rect((90.5, 10.25, 130., 30.75), blue);
+    painter.draw_rect((90., 40., 130., 60.), 2., blue);
+    painter.fill_rounded_rect((150., 10., 190., 30.), 6., blue);
+    painter.draw_rounded_rect((150., 40., 190., 60.), 20., 1.5, blue);
+    painter.fill_circle((25.5, 95.), 12., blue);
+    painter.draw_circle((25.5, 95.), 16., 1., orange);
+    // A star winds twice around its center, which is still filled once
+    let star = (0..5).map(|k| {
+        let angle = k as f32 * 4. * std::f32::consts::PI / 5. - std::f32::consts::FRAC_PI_2;
+        (80. + 18. * angle.cos(), 95. + 18. * angle.sin())
+    }).collect::<Vec<_>>();
+    painter.fill_polygon(&star, orange);
+    painter.draw_arc((130., 95.), 15., (0., 1.5 * std::f32::consts::PI), 2., CapStyle::Butt, blue);
+    painter.draw_quadratic_bezier([(160., 110.), (175., 75.), (190., 110.)], 2., CapStyle::Round, blue);
+    painter.draw_bezier([(195., 110.), (205., 70.), (220., 120.), (230., 80.)], 2., CapStyle::Round, orange);
+    painter.draw_text(&style, (120, 140), "Painter", BLACK, TextAlign::Center, TextAlign::Center);
+
+    // Nothing is drawn outside the clip, even by shapes which run off the image
+    painter.set_clip((200, 130, 300, 300));
+    assert_eq!(painter.clip(), (200, 130, 240, 160));
+    painter.fill_circle((210., 150.), 30., blue);
+    painter.draw_h_line(120, (0, 239), blue);
+    painter.reset_clip();
+    assert_eq!(painter.clip(), (0, 0, 240, 160));
+    assert_eq!(pixels[(199, 150)], WHITE);
+    assert_eq!(pixels[(210, 129)], WHITE);
+    assert_eq!(pixels[(210, 150)], blue);
+    assert_snapshot("painter", &pixels, DEFAULT_TOLERANCE);
+
+    // A translucent polyline is as light at its joins as along its segments
+    let mut pixels = image::ImageBuffer::from_pixel(40, 20, WHITE);
+    Painter::new(&mut pixels).draw_polyline(&[(5., 10.), (20., 10.), (35., 10.)], 4., CapStyle::Butt, orange);
+    assert_eq!(pixels[(20, 10)], pixels[(12, 10)]);
+}
+
+#[test]
+fn color() {
+    let close = |a: Color, b: Color| (a.r - b.r).abs() < 1e-3 && (a.g - b.g).abs() < 1e-3 && (a.b - b.b).abs() < 1e-3 && (a.a - b.a).abs() < 1e-3;
+    let red = Color::rgb(1., 0., 0.);
+    let blue = Color::rgb(0., 0., 1.);
+
+    // Source-over, with the result's alpha and premultiplied channels
+    assert!(close(red.with_alpha(0.5).over(blue), Color::rgb(0.5, 0., 0.5)));
+    assert!(close(red.with_alpha(0.5).over(blue.with_alpha(0.5)), Color::rgba(2. / 3., 0., 1. / 3., 0.75)));
+    assert!(close(red.with_alpha(0.).over(blue), blue));
+    assert_eq!(red.with_alpha(0.5).premultiplied(), [0.5, 0., 0., 0.5]);
+    assert!(close(Color::from_premultiplied([0.25, 0., 0., 0.5]), Color::rgba(0.5, 0., 0., 0.5)));
+    // Half black over white is a mid grey in linear light, which encodes brighter
+    let grey = Color::rgb(0., 0., 0.).with_alpha(0.5).over_linear(Color::rgb(1., 1., 1.));
+    assert!((grey.r - 0.7354).abs() < 1e-3);
+    assert_eq!(image::Rgba::from(Color::from(image::Rgba([31, 119, 180, 128]))), image::Rgba([31, 119, 180, 128]));
+
+    // Round trips through the other spaces
+    let orange = Color::from(image::Rgba([255, 127, 14, 255]));
+    let (h, s, l) = orange.to_hsl();
+    assert!((h - 28.).abs() < 0.5);
+    assert!(close(Color::from_hsl(h, s, l), orange));
+    let (h, s, v) = orange.to_hsv();
+    assert!(close(Color::from_hsv(h, s, v), orange));
+    let (l, a, b) = orange.to_oklab();
+    assert!(close(Color::from_oklab(l, a, b), orange));
+    let (l, a, b) = Color::rgb(1., 1., 1.).to_oklab();
+    assert!((l - 1.).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+
+    // Interpolation
+    assert!(close(red.lerp(blue, 0.5, ColorSpace::Srgb), Color::rgb(0.5, 0., 0.5)));
+    assert!(red.lerp(blue, 0.5, ColorSpace::LinearRgb).r > 0.7);
+    assert!(close(red.lerp(blue, 0.5, ColorSpace::Hsl), Color::rgb(1., 0., 1.)));
+    assert!(close(red.lerp(Color::rgb(0., 1., 0.), 0.5, ColorSpace::Hsv), Color::rgb(1., 1., 0.)));
+    assert!(close(red.lerp(red, 0.3, ColorSpace::Oklab), red));
+    // Fading out keeps the color rather than tinting it toward the transparent end
+    assert!(close(red.lerp(blue.with_alpha(0.), 0.5, ColorSpace::Srgb), red.with_alpha(0.5)));
+
+    // Translucent disks overlapping, composited on the encoded channels and in linear light
+    let mut pixels = image::ImageBuffer::from_pixel(240, 100, WHITE);
+    for (offset, linear) in [(0., false), (120., true)] {
+        let mut painter = Painter::new(&mut pixels);
+        painter.set_linear_blending(linear);
+        painter.fill_circle((offset + 45., 40.), 28., image::Rgba([31, 119, 180, 140]));
+        painter.fill_circle((offset + 75., 40.), 28., image::Rgba([255, 127, 14, 140]));
+        painter.fill_circle((offset + 60., 65.), 28., image::Rgba([44, 160, 44, 140]));
+    }
+    assert_snapshot("color", &pixels, DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn parse_colors() {
+    let rgba = |c: &str| parse_color(c).unwrap().0;
+    let near = |c: &str, expected: [u8; 4]| {
+        let actual = rgba(c);
+        assert!((0..4).all(|k| actual[k].abs_diff(expected[k]) <= 1), "{} is {:?}, not {:?}", c, actual, expected);
+    };
+    assert_eq!(rgba("#f80"), [255, 136, 0, 255]);
+    assert_eq!(rgba("#F808"), [255, 136, 0, 136]);
+    assert_eq!(rgba("#1f77b4"), [31, 119, 180, 255]);
+    assert_eq!(rgba("#1f77b480"), [31, 119, 180, 128]);
+    assert_eq!(rgba("steelblue"), [70, 130, 180, 255]);
+    assert_eq!(rgba("  RebeccaPurple "), [102, 51, 153, 255]);
+    assert_eq!(rgba("transparent"), [0, 0, 0, 0]);
+
+    assert_eq!(rgba("rgb(255, 0, 0)"), [255, 0, 0, 255]);
+    assert_eq!(rgba("rgba(255,0,0,0.5)"), [255, 0, 0, 128]);
+    assert_eq!(rgba("rgb(100% 50% 0% / 25%)"), [255, 128, 0, 64]);
+    assert_eq!(rgba("rgb(none 255 none)"), [0, 255, 0, 255]);
+    assert_eq!(rgba("hsl(120deg 100% 25%)"), [0, 128, 0, 255]);
+    assert_eq!(rgba("hsla(0.5turn, 100%, 50%, 1)"), [0, 255, 255, 255]);
+    assert_eq!(rgba("hwb(0 0% 0%)"), [255, 0, 0, 255]);
+    assert_eq!(rgba("hwb(90 50% 50%)"), [128, 128, 128, 255]);
+
+    near("lab(100 0 0)", [255, 255, 255, 255]);
+    near("lab(50% 0 0)", [119, 119, 119, 255]);
+    near("lch(54.29 106.84 40.85)", [255, 0, 0, 255]);
+    near("oklab(1 0 0 / 0.5)", [255, 255, 255, 128]);
+    near("oklch(62.8% 0.2577 29.23)", [255, 0, 0, 255]);
+    near("color(srgb 1 0.5 0)", [255, 128, 0, 255]);
+    near("color(srgb-linear 0.5 0.5 0.5)", [188, 188, 188, 255]);
+    for space in ["display-p3", "a98-rgb", "rec2020", "prophoto-rgb"] {
+        near(&format!("color({} 1 1 1)", space), [255, 255, 255, 255]);
+    }
+    near("color(xyz-d65 0.9505 1 1.089)", [255, 255, 255, 255]);
+    near("color(xyz-d50 0.9642 1 0.8252)", [255, 255, 255, 255]);
+
+    assert_eq!(parse_color("#12345"), Err(ColorParseError::InvalidHex("#12345".to_owned())));
+    assert!(matches!(parse_color("#ggg"), Err(ColorParseError::InvalidHex(_))));
+    assert!(matches!(parse_color("bleu"), Err(ColorParseError::UnknownName(_))));
+    assert!(matches!(parse_color("device-cmyk(0 0 0 1)"), Err(ColorParseError::UnknownFunction(_))));
+    assert!(matches!(parse_color("color(foo 1 1 1)"), Err(ColorParseError::UnknownFunction(_))));
+    for invalid in ["rgb(1 2)", "rgb(1 2 3", "rgb(1, 2, 3, 4, 5)", "hsl(10% 50% 50%)", "rgb(1 2 3 / )", "rgb(inf 0 0)"] {
+        assert!(matches!(parse_color(invalid), Err(ColorParseError::InvalidArguments(_))), "{}", invalid);
+    }
+
+    // An invalid color leaves the style alone
+    let mut style = Style::default(FONT_DATA);
+    assert!(style.set_color("cornflowerblue").is_ok());
+    assert!(style.set_color("#xyz").is_err());
+    assert_eq!(style.highlight_color, image::Rgba([100, 149, 237, 255]));
+}
+
+#[test]
+fn text_rotation() {
+    let style = Style::default(FONT_DATA);
+    let red = image::Rgba([214, 39, 40, 255]);
+    let mut pixels = image::ImageBuffer::from_pixel(360, 300, WHITE);
+    let mut painter = Painter::new(&mut pixels);
+
+    // A fan of angles about one anchor
+    for rotation in [0., 30., 60., 90., 135., 200.] {
+        painter.draw_rotated_text(&style, (90, 150), "Slanted", BLACK, TextAlign::Center, TextAlign::UpperLeft, rotation);
+    }
+    painter.fill_circle((90., 150.), 2.5, red);
+
+    // Every alignment, each turned about its own anchor
+    let aligns = [TextAlign::UpperLeft, TextAlign::Center, TextAlign::LowerRight];
+    for (i, va) in aligns.iter().enumerate() {
+        for (j, ha) in aligns.iter().enumerate() {
+            let pos = (220 + 50 * j as i32, 60 + 90 * i as i32);
+            painter.draw_rotated_text(&style, pos, "ab", BLACK, *va, *ha, -40.);
+            painter.fill_circle((pos.0 as f32, pos.1 as f32), 2.5, red);
+        }
+    }
+
+    // Quarter turns move whole pixels, so turning twice by 180 degrees gives the text back
+    let mut upright = image::ImageBuffer::from_pixel(80, 40, WHITE);
+    Painter::new(&mut upright).draw_text(&style, (40, 20), "Turn", BLACK, TextAlign::Center, TextAlign::Center);
+    let mut turned = image::ImageBuffer::from_pixel(80, 40, WHITE);
+    Painter::new(&mut turned).draw_rotated_text(&style, (40, 20), "Turn", BLACK, TextAlign::Center, TextAlign::Center, 360.);
+    assert_eq!(upright, turned);
+    let mut flipped = image::ImageBuffer::from_pixel(80, 40, WHITE);
+    Painter::new(&mut flipped).draw_rotated_text(&style, (40, 20), "Turn", BLACK, TextAlign::UpperLeft, TextAlign::UpperLeft, 180.);
+    let mut twice = image::ImageBuffer::from_pixel(80, 40, WHITE);
+    Painter::new(&mut twice).draw_image((0, 0), &image::imageops::rotate180(&flipped));
+    let mut upright = image::ImageBuffer::from_pixel(80, 40, WHITE);
+    Painter::new(&mut upright).draw_text(&style, (40, 20), "Turn", BLACK, TextAlign::UpperLeft, TextAlign::UpperLeft);
+    assert_eq!(upright, twice);
+
+    assert_snapshot("text_rotation", &pixels, DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn canvas_element() {
+    let style = Style::default(FONT_DATA);
+    let mut applet = Applet::headless(240, 160, style.clone());
+    let mut canvas = CanvasElement::new((Dim::Pixel(20), Dim::Pixel(10), Dim::Pixel(200), Dim::Pixel(140)), 240, 160);
+    // Four pixels per world unit, with the origin in the middle and y up
+    canvas.set_transform(Transform { center: (0., 0.), scale: 4., rotation: 0., y_up: true });
+    assert_eq!(canvas.world_to_pixel((0., 0.)), (120., 80.));
+    assert_eq!(canvas.world_to_pixel((5., 5.)), (140., 60.));
+    let turned = Transform { center: (1., 2.), scale: 3., rotation: 0.7, y_up: true };
+    let back = turned.pixel_to_world(turned.world_to_pixel((4., -3.), (200, 140)), (200, 140));
+    assert!((back.0 - 4.).abs() < 1e-4 && (back.1 + 3.).abs() < 1e-4);
+    // A quarter turn counterclockwise takes +x to straight up the screen
+    let quarter = Transform { rotation: std::f32::consts::FRAC_PI_2, ..canvas.transform() };
+    let up = quarter.world_to_pixel((5., 0.), (200, 140));
+    assert!((up.0 - 100.).abs() < 1e-4 && (up.1 - 50.).abs() < 1e-4);
+
+    let spring = (0..=16).map(|k| (-20. + k as f32 * 1.25, if k % 2 == 0 { -2. } else { 2. })).collect::<Vec<_>>();
+    canvas.set_background(Some(image::Rgba([240, 240, 240, 255])));
+    canvas.set_shapes(vec![
+        Shape::Polygon { points: vec![(-24., -10.), (24., -10.), (24., -14.), (-24., -14.)], style: ShapeStyle { fill: Some(image::Rgba([180, 180, 180, 255])), ..Default::default() } },
+        Shape::Line { points: spring, style: ShapeStyle::default() },
+        Shape::Circle { center: (4., 0.), radius: 4., style: ShapeStyle { fill: Some(image::Rgba([31, 119, 180, 255])), ..Default::default() } },
+        Shape::Arrow { from: (4., 0.), to: (4., 12.), head_size: 8., style: ShapeStyle { stroke: Some(image::Rgba([214, 39, 40, 255])), line_width: 2., ..Default::default() } },
+        Shape::Text { pos: (-24., 14.), text: "v".to_owned(), color: BLACK, va: TextAlign::Center, ha: TextAlign::UpperLeft },
+        // Reaches past the right edge, where it is cut off
+        Shape::Circle { center: (26., 6.), radius: 3., style: ShapeStyle::default() },
+    ]);
+
+    // Later shapes are picked over earlier ones, lines are picked from nearby, and text is never picked
+    assert_eq!(canvas.hit_test((136., 92.)), Some(2));
+    assert_eq!(canvas.hit_test(canvas.world_to_pixel((4., 10.))), Some(3));
+    assert_eq!(canvas.hit_test((40., 81.)), Some(1));
+    assert_eq!(canvas.hit_test(canvas.world_to_pixel((0., -12.))), Some(0));
+    assert_eq!(canvas.hit_test(canvas.world_to_pixel((-23., 14.))), None);
+    assert_eq!(canvas.hit_test((5., 5.)), None);
+    assert_snapshot("canvas", &render_element(&canvas, 240, 160, &style), DEFAULT_TOLERANCE);
+
+    // Drawn inside a smaller clip, the canvas stays within it, and the clip is restored afterwards
+    let mut pixels = image::ImageBuffer::from_pixel(240, 160, WHITE);
+    let mut painter = Painter::new(&mut pixels);
+    painter.set_clip((0, 0, 120, 160));
+    canvas.draw(&mut painter, &style);
+    assert_eq!(painter.clip(), (0, 0, 120, 160));
+    assert_eq!(pixels[(119, 20)], image::Rgba([240, 240, 240, 255]));
+    assert_eq!(pixels[(120, 20)], WHITE);
+
+    // Pressing a shape places a callback naming it, and hovering is remembered
+    let id = applet.add(canvas);
+    applet.mouse_move(136, 92);
+    assert_eq!(applet.get(id).hovered_shape(), Some(2));
+    applet.mouse_button_down(136, 92);
+    applet.mouse_button_up(136, 92);
+    applet.mouse_button_down(30, 20);
+    assert_eq!(applet.tick().collect::<Vec<_>>(), vec![Callback::ShapePressed(id, 2)]);
+}
+
+#[test]
+fn dynamic_plot_snapshot() {
+    let style = Style::default(FONT_DATA);
+    let xs = (0..20).map(|i| i as f32 / 2.).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| x.sin()).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::Line { xs: xs.clone(), ys: ys.clone(), ls: LineStyle::Solid, style: SeriesStyle::default() },
+        PlotCommand::Scatter { xs: xs.clone(), ys: ys.clone(), style: SeriesStyle::default() },
+        PlotCommand::SetXLabel { label: "t".to_owned() },
+        PlotCommand::SetYLabel { label: "x".to_owned() },
+    ], &style);
+    assert_snapshot("dynamic_plot", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_legend() {
+    let style = Style::default(FONT_DATA);
+    let xs = (0..20).map(|i| i as f32 / 2.).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| x * 0.5).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    let commands = vec![
+        PlotCommand::FillBetween { xs: xs.clone(), y1s: ys.iter().map(|y| y - 1.).collect(), y2s: ys.iter().map(|y| y + 1.).collect(), mask: None, style: SeriesStyle::default() },
+        PlotCommand::Line { xs: xs.clone(), ys: ys.clone(), ls: LineStyle::Dashed, style: SeriesStyle::default() },
+        PlotCommand::Scatter { xs: vec![2., 6.], ys: vec![1., 3.], style: SeriesStyle::default() },
+        PlotCommand::Legend { labels: vec![Some("band".to_owned()), Some("fit".to_owned()), None], loc: LegendLoc::Best },
+    ];
+
+    // The data rises to the right, so the best corner is the upper left
+    plot.plot(commands.clone(), &style);
+    assert_snapshot("plot_legend_best", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    let mut commands = commands;
+    commands.push(PlotCommand::Legend { labels: vec![None, Some("fit".to_owned()), Some("data".to_owned())], loc: LegendLoc::LowerRight });
+    plot.plot(commands, &style);
+    assert_snapshot("plot_legend_lower_right", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_series_styles() {
+    let style = Style::default(FONT_DATA);
+    let xs = vec![1., 2., 3., 4.];
+    let markers = [Marker::Circle, Marker::Square, Marker::Triangle, Marker::Cross, Marker::Plus];
+    let mut commands = markers.iter().enumerate().map(|(i, marker)| PlotCommand::Scatter {
+        xs: xs.clone(),
+        ys: vec![i as f32; 4],
+        style: SeriesStyle { marker: *marker, marker_size: Some(5.), ..Default::default() },
+    }).collect::<Vec<_>>();
+    commands.push(PlotCommand::Line {
+        xs: xs.clone(), ys: vec![4.5, 5., 4.5, 5.], ls: LineStyle::Solid,
+        style: SeriesStyle { color: Some(image::Rgba([0, 0, 0, 255])), alpha: Some(0.5), line_width: Some(6.), ..Default::default() },
+    });
+    commands.push(PlotCommand::SetYLim { low: -1., high: 6. });
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(commands, &style);
+    assert_snapshot("plot_series_styles", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // At this size the y ticks flip between steps of 2 and 2.5 as the margins change, and the layout has to settle
+    // on ticks whose labels fit
+    for (left, top, right, bottom) in plot.tick_label_boxes(&style) {
+        assert!(left >= 0 && top >= 0 && right <= 320 && bottom <= 240, "{:?}", (left, top, right, bottom));
+    }
+}
+
+#[test]
+fn dasher() {
+    // The phase carries over from one segment to the next
+    let mut dasher = Dasher::new(&[4., 2.]);
+    let pieces = dasher.dash(&[(0., 0.), (5., 0.), (5., 5.)]);
+    assert_eq!(pieces, vec![
+        vec![(0., 0.), (4., 0.)],
+        vec![(5., 1.), (5., 5.)],
+    ]);
+
+    // ...and from one call to the next
+    let pieces = dasher.dash(&[(0., 10.), (4., 10.)]);
+    assert_eq!(pieces, vec![vec![(2., 10.), (4., 10.)]]);
+
+    // Zero-length dashes are dots
+    let pieces = Dasher::new(&[0., 3.]).dash(&[(0., 0.), (7., 0.)]);
+    assert_eq!(pieces, vec![
+        vec![(0., 0.), (0., 0.)],
+        vec![(3., 0.), (3., 0.)],
+        vec![(6., 0.), (6., 0.)],
+    ]);
+
+    // Odd patterns are repeated
+    let pieces = Dasher::new(&[2., 1., 1.]).dash(&[(0., 0.), (7., 0.)]);
+    assert_eq!(pieces, vec![
+        vec![(0., 0.), (2., 0.)],
+        vec![(3., 0.), (4., 0.)],
+        vec![(6., 0.), (7., 0.)],
+    ]);
+
+    // Degenerate patterns are solid
+    let pieces = Dasher::new(&[0., 0.]).dash(&[(0., 0.), (7., 0.)]);
+    assert_eq!(pieces, vec![vec![(0., 0.), (7., 0.)]]);
+
+    // So are patterns too fine to see, which would otherwise take forever to step through
+    let pieces = Dasher::new(&[1e-7, 1e-7]).dash(&[(0., 0.), (1000., 0.)]);
+    assert_eq!(pieces, vec![vec![(0., 0.), (1000., 0.)]]);
+
+    // A segment too long to split is drawn whole, keeping the phase
+    let pieces = Dasher::new(&[4., 2.]).dash(&[(0., 0.), (1e9, 0.)]);
+    assert_eq!(pieces, vec![vec![(0., 0.), (1e9, 0.)]]);
+
+    // Repeated points add nothing, and never give NaN points
+    let pieces = Dasher::new(&[0., 3.]).dash(&[(0., 0.), (0., 0.), (4., 0.), (4., 0.)]);
+    assert_eq!(pieces, vec![
+        vec![(0., 0.), (0., 0.)],
+        vec![(3., 0.), (3., 0.)],
+    ]);
+}
+
+#[test]
+fn plot_line_styles() {
+    let style = Style::default(FONT_DATA);
+    let xs = (0..=40).map(|i| i as f32 / 4.).collect::<Vec<_>>();
+    let line_styles = [
+        (LineStyle::Solid, CapStyle::Round),
+        (LineStyle::Dashed, CapStyle::Butt),
+        (LineStyle::Dashed, CapStyle::Round),
+        (LineStyle::Dotted, CapStyle::Round),
+        (LineStyle::DashDot, CapStyle::Butt),
+        (LineStyle::Custom(vec![12., 3., 3., 3., 3., 3.]), CapStyle::Butt),
+    ];
+    let commands = line_styles.into_iter().enumerate().map(|(i, (ls, cap))| PlotCommand::Line {
+        xs: xs.clone(),
+        ys: xs.iter().map(|x| i as f32 + 0.3 * x.sin()).collect(),
+        ls,
+        style: SeriesStyle { cap, line_width: Some(2. + i as f32 / 2.), ..Default::default() },
+    }).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(commands, &style);
+    assert_snapshot("plot_line_styles", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // A pattern too fine to see draws as a solid line, rather than stepping through millions of dashes
+    let fine = PlotCommand::Line { xs: vec![0., 1.], ys: vec![0., 1.], ls: LineStyle::Custom(vec![1e-6, 1e-6]), style: SeriesStyle::default() };
+    let solid = PlotCommand::Line { xs: vec![0., 1.], ys: vec![0., 1.], ls: LineStyle::Solid, style: SeriesStyle::default() };
+    plot.plot(vec![fine], &style);
+    let fine = render_element(&plot, 320, 240, &style);
+    plot.plot(vec![solid], &style);
+    assert_eq!(fine, render_element(&plot, 320, 240, &style));
+}
+
+#[test]
+fn plot_scales() {
+    let style = Style::default(FONT_DATA);
+    // A power law, with a non-positive point which the log axes must drop
+    let xs = (0..=30).map(|i| 10f32.powf(i as f32 / 10.)).chain([0.]).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| 5. * x.powf(-1.5)).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::Line { xs: xs.clone(), ys: ys.clone(), ls: LineStyle::Solid, style: SeriesStyle::default() },
+        PlotCommand::Scatter { xs: xs.clone(), ys: ys.clone(), style: SeriesStyle::default() },
+        PlotCommand::SetXScale { scale: AxisScale::Log },
+        PlotCommand::SetYScale { scale: AxisScale::Log },
+    ], &style);
+    let (x_lim, y_lim) = (plot.x_lim(), plot.y_lim());
+    assert!(x_lim.0 > 0. && x_lim.0 < 1. && x_lim.1 > 1000., "{:?}", x_lim);
+    assert!(y_lim.0 > 0. && y_lim.0 < 5e-4 && y_lim.1 > 5., "{:?}", y_lim);
+    assert_snapshot("plot_log_scale", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // Symlog shows both signs over several decades
+    let xs = (-40..=40).map(|i| i as f32 / 4.).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| x.powi(3)).collect::<Vec<_>>();
+    plot.plot(vec![
+        PlotCommand::Line { xs, ys, ls: LineStyle::Solid, style: SeriesStyle::default() },
+        PlotCommand::SetYScale { scale: AxisScale::SymLog { linthresh: 1. } },
+    ], &style);
+    assert_snapshot("plot_symlog_scale", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // Panning a log axis moves it by a constant factor
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::SetXScale { scale: AxisScale::Log },
+        PlotCommand::SetXLim { low: 1., high: 100. },
+    ], &style);
+    plot.gesture(Gesture::Pan { dx: -(plot.axes_rect().2 as f32) / 2., dy: 0. });
+    let (low, high) = plot.x_lim();
+    assert!((low - 10.).abs() < 1e-3 && (high - 1000.).abs() < 1e-1, "{:?}", plot.x_lim());
+}
+
+#[test]
+fn plot_ticks() {
+    let style = Style::default(FONT_DATA);
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+
+    // Steps of 0.1 are written with one decimal, rather than as 0.30000001
+    let xs = vec![0.1, 0.2, 0.3, 0.4, 0.5];
+    // Large values close together are written relative to an offset, and tiny values with a multiplier
+    let ys = vec![100000.1, 100000.3, 100000.2, 100000.5, 100000.4];
+    plot.plot(vec![
+        PlotCommand::Scatter { xs: xs.iter().map(|x| x * 1e-4).collect(), ys, style: SeriesStyle::default() },
+        PlotCommand::SetXLabel { label: "x".to_owned() },
+    ], &style);
+    assert_snapshot("plot_ticks_offset", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    plot.plot(vec![
+        PlotCommand::Line { xs: xs.clone(), ys: xs.clone(), ls: LineStyle::Solid, style: SeriesStyle::default() },
+    ], &style);
+    assert_snapshot("plot_ticks_decimals", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // A single point, or limits with no span, still give a usable axis
+    plot.plot(vec![
+        PlotCommand::Scatter { xs: vec![3.], ys: vec![3.], style: SeriesStyle::default() },
+        PlotCommand::SetYLim { low: 0., high: 0. },
+    ], &style);
+    let (x_lim, y_lim) = (plot.x_lim(), plot.y_lim());
+    assert!(x_lim.0 < 3. && x_lim.1 > 3., "{:?}", x_lim);
+    assert!(y_lim.0 < 0. && y_lim.1 > 0., "{:?}", y_lim);
+}
+
+#[test]
+fn plot_layout() {
+    let style = Style::default(FONT_DATA);
+    let xs = (0..=20).map(|i| i as f32 * 50.).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| 20. + 0.01 * x).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(400), Dim::Pixel(300)), 400, 300);
+    plot.plot(vec![
+        PlotCommand::Line { xs: xs.clone(), ys: ys.clone(), ls: LineStyle::Solid, style: SeriesStyle::default() },
+        PlotCommand::Scatter { xs: xs.clone(), ys: ys.iter().map(|y| y - 2.).collect(), style: SeriesStyle::default() },
+        PlotCommand::SetTitle { title: "Heating".to_owned() },
+        PlotCommand::SetXLabel { label: "time (s)".to_owned() },
+        PlotCommand::SetYLabel { label: "temperature (°C)".to_owned() },
+        PlotCommand::SecondaryYAxis { label: "°F".to_owned(), forward: |c| c * 1.8 + 32., inverse: |f| (f - 32.) / 1.8 },
+        PlotCommand::Legend { labels: vec![Some("model".to_owned()), Some("data".to_owned())], loc: LegendLoc::OutsideRight },
+    ], &style);
+    assert_snapshot("plot_layout", &render_element(&plot, 400, 300, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_axes_style() {
+    let style = Style::default(FONT_DATA);
+    let xs = (0..=40).map(|i| i as f32 / 4.).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| x.sin()).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::Line { xs, ys, ls: LineStyle::Solid, style: SeriesStyle::default() },
+        PlotCommand::SetTitle { title: "Boxed".to_owned() },
+        PlotCommand::SetXLabel { label: "t".to_owned() },
+        PlotCommand::SetYLabel { label: "hidden".to_owned() },
+        PlotCommand::SetAxesStyle { style: AxesStyle {
+            spines: Spines::Box,
+            tick_direction: TickDirection::Out,
+            show_y: false,
+            major_grid: Some(GridStyle::default()),
+            minor_grid: Some(GridStyle { alpha: 0.5, ls: LineStyle::Dotted, ..Default::default() }),
+            ..Default::default()
+        } },
+    ], &style);
+    assert_snapshot("plot_axes_style", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_heatmap() {
+    let style = Style::default(FONT_DATA);
+    assert_eq!(Colormap::from_name("Magma"), Some(Colormap::Magma));
+    assert_eq!(Colormap::from_name("jet"), None);
+    assert_eq!(Colormap::Greys.color(0.), WHITE);
+    assert_eq!(Colormap::Greys.color(2.), Colormap::Greys.color(1.));
+
+    // A Gaussian probability density, smoothed between cells
+    let (rows, cols) = (12, 16);
+    let values = (0..rows * cols).map(|k| {
+        let x = ((k % cols) as f32 + 0.5) / cols as f32 * 4. - 2.;
+        let y = ((k / cols) as f32 + 0.5) / rows as f32 * 3. - 1.5;
+        (-(x * x + 2. * y * y) / 2.).exp() / (2. * std::f32::consts::PI)
+    }).collect::<Vec<_>>();
+    let grid = GridData::new(values, rows, cols, (-2., 2., -1.5, 1.5));
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::ImShow { grid: grid.clone(), style: ImageStyle {
+            interpolation: Interpolation::Bilinear,
+            label: "density".to_owned(),
+            ..Default::default()
+        } },
+        PlotCommand::SetXLabel { label: "x".to_owned() },
+        PlotCommand::SetYLabel { label: "y".to_owned() },
+    ], &style);
+    assert_snapshot("plot_heatmap", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // Decades of a field, with cells of one flat color and a value the log cannot show left empty
+    let values = (0..20).map(|k| if k == 7 { 0. } else { 10f32.powf(k as f32 / 5. - 1.) }).collect();
+    let grid = GridData::new(values, 4, 5, (0., 5., 0., 4.));
+    plot.plot(vec![
+        PlotCommand::ImShow { grid, style: ImageStyle {
+            colormap: Colormap::Magma,
+            norm: AxisScale::Log,
+            vmin: Some(0.1),
+            ..Default::default()
+        } },
+    ], &style);
+    assert_snapshot("plot_heatmap_log", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_contour() {
+    let style = Style::default(FONT_DATA);
+    // A double well, whose levels close around each minimum and merge above the barrier
+    let (rows, cols) = (30, 40);
+    let values = (0..rows * cols).map(|k| {
+        let x = ((k % cols) as f32 + 0.5) / cols as f32 * 4. - 2.;
+        let y = ((k / cols) as f32 + 0.5) / rows as f32 * 3. - 1.5;
+        (x * x - 1.).powi(2) + y * y
+    }).collect::<Vec<_>>();
+    let grid = GridData::new(values, rows, cols, (-2., 2., -1.5, 1.5));
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::Contour { grid: grid.clone(), levels: vec![0.25, 0.5, 1., 2.], style: ContourStyle {
+            labels: true,
+            ..Default::default()
+        } },
+    ], &style);
+    assert_snapshot("plot_contour", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    plot.plot(vec![
+        PlotCommand::Contour { grid, levels: vec![0., 0.25, 0.5, 1., 2., 4.], style: ContourStyle {
+            colormap: Colormap::Magma,
+            color: Some(image::Rgba([0, 0, 0, 255])),
+            line_width: 1.,
+            filled: true,
+            ..Default::default()
+        } },
+    ], &style);
+    assert_snapshot("plot_contour_filled", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_hist() {
+    let style = Style::default(FONT_DATA);
+    let (edges, counts) = histogram(&[0., 0.5, 1., 1., 2., f32::NAN, 5.], &Bins::Edges(vec![0., 1., 2.]), &HistOptions::default());
+    assert_eq!(edges, vec![0., 1., 2.]);
+    assert_eq!(counts, vec![2., 3.]);
+    let (edges, counts) = histogram(&[0., 1., 2., 3.], &Bins::Sturges, &HistOptions { weights: Some(vec![1., 2., 3., 4.]), cumulative: true, ..Default::default() });
+    assert_eq!(edges.len(), 4);
+    assert_eq!(counts, vec![1., 3., 10.]);
+    // An explicit count is kept however large, while the rules are capped so that an outlier cannot explode them
+    let (edges, _) = histogram(&[0., 1.], &Bins::Count(1500), &HistOptions::default());
+    assert_eq!(edges.len(), 1501);
+    let (edges, _) = histogram(&[0., 1.], &Bins::Count(0), &HistOptions::default());
+    assert_eq!(edges.len(), 2);
+    let outlier = (0..100).map(|i| i as f32 / 100.).chain([1e6]).collect::<Vec<_>>();
+    let (edges, _) = histogram(&outlier, &Bins::FreedmanDiaconis, &HistOptions::default());
+    assert_eq!(edges.len(), 1001);
+
+    // Normal samples from a Box-Muller transform of a fixed sequence
+    let mut seed = 12345u32;
+    let mut uniform = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 8) as f32 / (1 << 24) as f32 + 1e-7
+    };
+    let normal = (0..2000).map(|_| (-2. * uniform().ln()).sqrt() * (2. * std::f32::consts::PI * uniform()).cos()).collect::<Vec<_>>();
+    let density = HistOptions { density: true, ..Default::default() };
+    let (edges, heights) = histogram(&normal, &Bins::FreedmanDiaconis, &density);
+    let area = heights.iter().zip(edges.windows(2)).map(|(h, e)| h * (e[1] - e[0])).sum::<f32>();
+    assert!((area - 1.).abs() < 1e-4);
+
+    let xs = (0..=80).map(|i| i as f32 / 10. - 4.).collect::<Vec<_>>();
+    let pdf = xs.iter().map(|x| (-x * x / 2.).exp() / (2. * std::f32::consts::PI).sqrt()).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::Hist { samples: normal.clone(), bins: Bins::FreedmanDiaconis, options: density.clone(), style: SeriesStyle { alpha: Some(0.6), ..Default::default() } },
+        PlotCommand::Hist { samples: normal.iter().map(|x| x * 0.5 + 1.).collect(), bins: Bins::Count(20), options: HistOptions {
+            histtype: HistType::Step,
+            ..density
+        }, style: SeriesStyle::default() },
+        PlotCommand::Line { xs, ys: pdf, ls: LineStyle::Dashed, style: SeriesStyle { color: Some(image::Rgba([0, 0, 0, 255])), ..Default::default() } },
+        PlotCommand::Legend { labels: vec![Some("σ = 1".to_owned()), Some("σ = 0.5".to_owned()), None], loc: LegendLoc::UpperLeft },
+    ], &style);
+    assert_snapshot("plot_hist", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    plot.plot(vec![
+        PlotCommand::Hist { samples: normal, bins: Bins::Scott, options: HistOptions {
+            cumulative: true,
+            density: true,
+            histtype: HistType::StepFilled,
+            ..Default::default()
+        }, style: SeriesStyle { alpha: Some(0.5), ..Default::default() } },
+        PlotCommand::SetYLim { low: 0., high: 0.8 },
+    ], &style);
+    assert_snapshot("plot_hist_cumulative", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_error_bars() {
+    let style = Style::default(FONT_DATA);
+    let xs = vec![1., 2., 3., 4., 5.];
+    let ys = vec![2., 3.5, 3., 5., 4.5];
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::ErrorBar { xs: xs.clone(), ys: ys.clone(), options: ErrorBarOptions {
+            x_errs: Errors::Symmetric(vec![0.2, 0.3, 0.2, 0.4, 0.3]),
+            y_errs: Errors::Asymmetric { lower: vec![0.5, 1., 0.3, 0.5, 2.], upper: vec![0.2, 0.5, 0.3, 1.5, 0.5] },
+            ..Default::default()
+        }, style: SeriesStyle { marker: Marker::Square, ..Default::default() } },
+        PlotCommand::ErrorBar { xs: xs.iter().map(|x| x + 0.5).collect(), ys: ys.iter().map(|y| y - 1.).collect(), options: ErrorBarOptions {
+            y_errs: Errors::Symmetric(vec![0.4; 5]),
+            cap_size: 0.,
+            markers: false,
+            ..Default::default()
+        }, style: SeriesStyle { line_width: Some(1.), ..Default::default() } },
+        PlotCommand::Legend { labels: vec![Some("asymmetric".to_owned()), Some("plain".to_owned())], loc: LegendLoc::OutsideRight },
+    ], &style);
+    // The bars are inside the automatic limits
+    assert!(plot.y_lim().0 < 1. && plot.y_lim().1 > 6.5);
+    assert!(plot.x_lim().0 < 0.8 && plot.x_lim().1 > 5.5);
+    assert_snapshot("plot_error_bars", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_auto_limits() {
+    let style = Style::default(FONT_DATA);
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    let band = PlotCommand::FillBetween { xs: vec![10., 20.], y1s: vec![5., 6.], y2s: vec![7., 8.], mask: None, style: SeriesStyle::default() };
+
+    // A lone band sets the limits, with the default margin
+    plot.plot(vec![band.clone()], &style);
+    assert_eq!(plot.x_lim(), (9.7, 20.3));
+    assert_eq!(plot.y_lim(), (4.91, 8.09));
+
+    // Bars stand on zero
+    plot.plot(vec![PlotCommand::Bar { edges: vec![1., 2., 3.], ys: vec![4., 6.], style: SeriesStyle::default() }], &style);
+    assert!(plot.y_lim().0 < 0. && plot.y_lim().1 > 6.);
+
+    // Data with nothing finite falls back on the default limits rather than panicking
+    assert!(fnanmin(&[f32::NAN, f32::INFINITY]).is_nan());
+    assert!(fnanmax(&[]).is_nan());
+    plot.plot(vec![PlotCommand::Line { xs: vec![f32::NAN; 3], ys: vec![f32::NAN; 3], ls: LineStyle::Solid, style: SeriesStyle::default() }], &style);
+    assert_eq!((plot.x_lim(), plot.y_lim()), ((-1., 1.), (-1., 1.)));
+
+    // No margin, with zero brought into view on y only
+    plot.plot(vec![band.clone(), PlotCommand::SetAutoLimits { limits: AutoLimits {
+        x_margin: 0.,
+        y_margin: 0.,
+        y_include_zero: true,
+        ..Default::default()
+    } }], &style);
+    assert_eq!((plot.x_lim(), plot.y_lim()), ((10., 20.), (0., 8.)));
+
+    // Limits set by hand win
+    plot.plot(vec![band, PlotCommand::SetXLim { low: 0., high: 100. }], &style);
+    assert_eq!(plot.x_lim(), (0., 100.));
+}
+
+#[test]
+fn plot_fill() {
+    let style = Style::default(FONT_DATA);
+    // Decreasing xs, curves which cross, and a mask leaving out the middle
+    let xs = (0..=60).rev().map(|i| i as f32 / 10.).collect::<Vec<_>>();
+    let sin = xs.iter().map(|x| x.sin()).collect::<Vec<_>>();
+    let cos = xs.iter().map(|x| x.cos()).collect::<Vec<_>>();
+    let mask = xs.iter().map(|x| !(2.5..3.5).contains(x)).collect::<Vec<_>>();
+    let ys = (0..=40).map(|i| i as f32 / 40. * 2. - 1.).collect::<Vec<_>>();
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.plot(vec![
+        PlotCommand::FillBetween { xs: xs.clone(), y1s: sin, y2s: cos, mask: Some(mask), style: SeriesStyle::default() },
+        PlotCommand::FillBetweenX { ys: ys.clone(), x1s: ys.iter().map(|y| 4. + y * y).collect(), x2s: vec![5.5; ys.len()], mask: None, style: SeriesStyle::default() },
+        // Far past the limits on every side
+        PlotCommand::FillBetween { xs: vec![-10., 10.], y1s: vec![-0.2, -0.2], y2s: vec![-10., -10.], mask: None, style: SeriesStyle { alpha: Some(0.15), ..Default::default() } },
+        PlotCommand::SetXLim { low: 0., high: 6. },
+        PlotCommand::SetYLim { low: -1.2, high: 1.2 },
+    ], &style);
+    assert_snapshot("plot_fill", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+}
+
+#[test]
+fn plot_tooltip() {
+    let style = Style::default(FONT_DATA);
+    let mut plot = DynamicPlot::new((Dim::Pixel(0), Dim::Pixel(0), Dim::Pixel(320), Dim::Pixel(240)), 320, 240);
+    plot.set_crosshair(true);
+    plot.plot(vec![
+        PlotCommand::SetXLim { low: 0., high: 4. },
+        PlotCommand::SetYLim { low: 0., high: 4. },
+        PlotCommand::Scatter { xs: vec![1., 2., 3.], ys: vec![1., 2.5, 3.], style: SeriesStyle::default() },
+    ], &style);
+
+    // Just off (2, 2.5)
+    let (left, top, width, height) = plot.axes_rect();
+    let (x, y) = (left as f32 + 0.5 * width as f32, top as f32 + (1. - 2.5 / 4.) * height as f32);
+    plot.mouse_move(Mouse { x: x as u32 + 3, y: y as u32 - 2, down: false, id: MOUSE_POINTER });
+    assert_eq!(plot.hovered_point(), Some((2., 2.5)));
+    assert_snapshot("plot_tooltip", &render_element(&plot, 320, 240, &style), DEFAULT_TOLERANCE);
+
+    // Far from any point
+    plot.mouse_move(Mouse { x: 300, y: 180, down: false, id: MOUSE_POINTER });
+    assert_eq!(plot.hovered_point(), None);
+}
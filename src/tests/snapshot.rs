@@ -0,0 +1,93 @@
+//! Golden-image snapshot testing.
+//!
+//! Each snapshot is stored as `src/tests/snapshots/<name>.png`. When a rendered image differs from its
+//! snapshot by more than the tolerance, the rendered image and a diff image are written to
+//! `target/snapshots/` and the test fails. A missing snapshot fails too. Run the tests with
+//! `UPDATE_SNAPSHOTS=1` to accept the new renders as the snapshots, or to create missing ones.
+
+use std::path::PathBuf;
+
+use image::{ImageBuffer, Rgba};
+
+use crate::{element::Element, style::WHITE, Painter, Style};
+
+/// Default largest difference allowed in any channel of any pixel
+pub const DEFAULT_TOLERANCE: u8 = 2;
+
+const DIFF_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src").join("tests").join("snapshots")
+}
+
+fn output_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("snapshots")
+}
+
+/// Draw a single element onto a blank white image of the given size
+pub fn render_element(element: &dyn Element, width: u32, height: u32, style: &Style) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let mut pixels = ImageBuffer::from_pixel(width, height, WHITE);
+    let mut painter = Painter::new(&mut pixels);
+    painter.set_linear_blending(style.linear_blending);
+    element.draw(&mut painter, style);
+    pixels
+}
+
+/// Compare two images. Returns the number of pixels which differ by more than `tolerance` in any channel,
+/// together with an image highlighting those pixels in red over a faded copy of `expected`.
+pub fn diff_images(expected: &ImageBuffer<Rgba<u8>, Vec<u8>>, actual: &ImageBuffer<Rgba<u8>, Vec<u8>>, tolerance: u8) -> (usize, ImageBuffer<Rgba<u8>, Vec<u8>>) {
+    assert_eq!(expected.dimensions(), actual.dimensions(), "Snapshot dimensions do not match");
+    let mut count = 0;
+    let diff = ImageBuffer::from_fn(expected.width(), expected.height(), |i, j| {
+        let a = expected[(i, j)];
+        let b = actual[(i, j)];
+        let differs = a.0.iter().zip(b.0.iter()).any(|(x, y)| x.abs_diff(*y) > tolerance);
+        if differs {
+            count += 1;
+            DIFF_COLOR
+        } else {
+            // Fade the unchanged pixels so the differences stand out
+            let fade = |c: u8| 255 - (255 - c) / 4;
+            Rgba([fade(a.0[0]), fade(a.0[1]), fade(a.0[2]), 255])
+        }
+    });
+    (count, diff)
+}
+
+/// Check `actual` against the snapshot called `name`, panicking if any pixel differs by more than `tolerance` or if
+/// the snapshot is missing. With `UPDATE_SNAPSHOTS` set, `actual` is written as the snapshot instead.
+pub fn assert_snapshot(name: &str, actual: &ImageBuffer<Rgba<u8>, Vec<u8>>, tolerance: u8) {
+    let path = snapshot_dir().join(format!("{}.png", name));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if update {
+        std::fs::create_dir_all(snapshot_dir()).unwrap();
+        actual.save(&path).expect("Could not write the snapshot");
+        return;
+    }
+    if !path.exists() {
+        panic!(
+            "Snapshot {} is missing. Run the test with UPDATE_SNAPSHOTS=1 to create {}", name, path.display()
+        );
+    }
+
+    let expected = image::open(&path).expect("Could not read the snapshot").to_rgba8();
+    if expected.dimensions() != actual.dimensions() {
+        panic!(
+            "Snapshot {} is {:?} but the render is {:?}", name, expected.dimensions(), actual.dimensions()
+        );
+    }
+    let (count, diff) = diff_images(&expected, actual, tolerance);
+    if count == 0 {
+        return;
+    }
+
+    std::fs::create_dir_all(output_dir()).unwrap();
+    let actual_path = output_dir().join(format!("{}.actual.png", name));
+    let diff_path = output_dir().join(format!("{}.diff.png", name));
+    actual.save(&actual_path).expect("Could not write the render");
+    diff.save(&diff_path).expect("Could not write the diff");
+    panic!(
+        "Snapshot {} differs in {} pixels. See {} and {}",
+        name, count, actual_path.display(), diff_path.display()
+    );
+}
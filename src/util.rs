@@ -1,5 +1,7 @@
 use image::Rgba;
 
+use crate::{Color, ColorParseError};
+
 #[derive(Clone, Copy, Debug)]
 pub enum Dim {
     Pixel(u32),
@@ -20,17 +22,18 @@ impl Dim {
     }
 }
 
+/// Read a CSS color, such as `#1f77b4`, `steelblue`, `rgb(31 119 180 / 50%)` or `oklch(0.6 0.1 240)`.
+/// See `Color::from_str` for the syntaxes understood.
+pub fn parse_color(color: &str) -> Result<Rgba<u8>, ColorParseError> {
+    color.parse::<Color>().map(Rgba::from)
+}
+
+/// Like `parse_color`, but panics if the color is invalid. For colors written into the program.
 pub fn hex_to_rgba(color: &str) -> Rgba<u8> {
-    if !color.starts_with('#') {
-        panic!("The color must begin with a hash");
-    }
-    let r = u8::from_str_radix(&color[1..3], 16).unwrap();
-    let g = u8::from_str_radix(&color[3..5], 16).unwrap();
-    let b = u8::from_str_radix(&color[5..7], 16).unwrap();
-    Rgba([r, g, b, 255])
+    parse_color(color).unwrap_or_else(|e| panic!("{}", e))
 }
 
-// Find the minimum finite value of an array
+// Find the minimum finite value of an array, or NaN if it has none
 pub fn fnanmin(xs: &[f32]) -> f32 {
     let mut out = None;
     for x in xs {
@@ -40,10 +43,10 @@ pub fn fnanmin(xs: &[f32]) -> f32 {
             Some(o) => Some(o.min(*x)),
         }
     }
-    out.expect("This array contained no finite values")
+    out.unwrap_or(f32::NAN)
 }
 
-// Find the maximum finite value of an array
+// Find the maximum finite value of an array, or NaN if it has none
 pub fn fnanmax(xs: &[f32]) -> f32 {
     let mut out = None;
     for x in xs {
@@ -53,14 +56,10 @@ pub fn fnanmax(xs: &[f32]) -> f32 {
             Some(o) => Some(o.max(*x)),
         }
     }
-    out.expect("This array contained no finite values")
+    out.unwrap_or(f32::NAN)
 }
 
+/// Composite `a` over `b`, with the opacity of `a` multiplied by `alpha`
 pub fn blend_color(a: Rgba<u8>, b: Rgba<u8>, alpha: f32) -> Rgba<u8> {
-    Rgba([
-        (a.0[0] as f32 * alpha) as u8 + (b.0[0] as f32 * (1. - alpha)) as u8,
-        (a.0[1] as f32 * alpha) as u8 + (b.0[1] as f32 * (1. - alpha)) as u8,
-        (a.0[2] as f32 * alpha) as u8 + (b.0[2] as f32 * (1. - alpha)) as u8,
-        255
-    ])
+    Color::from(a).faded(alpha).over(Color::from(b)).into()
 }
\ No newline at end of file